@@ -1,4 +1,5 @@
 use crate::auth::{login, xbox};
+use crate::common::utils::network;
 use crate::data::constants;
 use crate::data::models::{BaseEventPayload, LoginEventPayload, MinecraftAccount};
 use reqwest::Client;
@@ -53,6 +54,11 @@ pub async fn get_bearer_token(code: &str, app: &tauri::AppHandle, is_refresh: bo
 }
 
 pub async fn refresh_bearer_tokens(app: &tauri::AppHandle) {
+    // keep the cached access tokens, they are still good enough to launch
+    if network::is_offline() {
+        return;
+    }
+
     let accounts: Vec<MinecraftAccount> = login::get_accounts();
 
     for account in accounts.iter() {
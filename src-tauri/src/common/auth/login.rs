@@ -90,15 +90,29 @@ pub fn get_accounts() -> Vec<MinecraftAccount> {
         let entry: DirEntry = entry.unwrap();
         let path: PathBuf = entry.path();
 
-        let contents: Vec<u8> = fs::read(&path).unwrap();
-        let account: MinecraftAccount = serde_json::from_slice(&contents).unwrap();
-
-        accounts.push(account);
+        if let Some(account) = read_account(&path) {
+            accounts.push(account);
+        }
     }
 
     accounts
 }
 
+fn read_account(path: &PathBuf) -> Option<MinecraftAccount> {
+    if path.extension().unwrap_or_default() != "json" {
+        return None;
+    }
+
+    let contents: Vec<u8> = fs::read(path).ok()?;
+    match serde_json::from_slice(&contents) {
+        Ok(account) => Some(account),
+        Err(_) => {
+            file::quarantine(path);
+            None
+        }
+    }
+}
+
 pub fn remove_account(uuid: &str) {
     file::delete(format!("launcher/auth/{}.json", uuid).as_str());
 }
@@ -118,25 +132,27 @@ pub fn get_active_account() -> Result<MinecraftAccount, Box<dyn std::error::Erro
     )));
 }
 
-pub fn set_active_account(uuid: &str) {
+pub fn set_active_account(uuid: &str) -> Result<(), Box<dyn std::error::Error>> {
     let auth_path: PathBuf = directory::check_directory_sync("launcher/auth");
 
-    for entry in fs::read_dir(auth_path).unwrap() {
-        let entry: DirEntry = entry.unwrap();
+    for entry in fs::read_dir(auth_path)? {
+        let entry: DirEntry = entry?;
         let path: PathBuf = entry.path();
 
-        let contents: Vec<u8> = fs::read(&path).unwrap();
-        let mut account: MinecraftAccount = serde_json::from_slice(&contents).unwrap();
+        let mut account: MinecraftAccount = match read_account(&path) {
+            Some(account) => account,
+            None => continue,
+        };
         if account.uuid == uuid {
             account.active = true;
         } else {
             account.active = false;
         }
 
-        file::write_vec(
-            &serde_json::to_vec(&account).unwrap(),
-            format!("launcher/auth/{}.json", { account.uuid }).as_str(),
-        )
-        .unwrap();
+        file::write_value(
+            &account,
+            format!("launcher/auth/{}.json", account.uuid).as_str(),
+        )?;
     }
+    Ok(())
 }
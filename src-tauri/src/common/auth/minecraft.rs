@@ -95,10 +95,10 @@ pub async fn get_account_info(
     } else {
         let avatar: String = user_avatar(uuid).await;
         let account_info: MinecraftAccount =
-            match file::read_as_vec(format!("launcher/auth/{uuid}.json").as_str()).await {
-                Ok(account_bytes) => {
-                    let mut account_info: MinecraftAccount =
-                        serde_json::from_slice(&account_bytes).unwrap();
+            match file::read_as_value::<MinecraftAccount>(&format!("launcher/auth/{uuid}.json"))
+                .await
+            {
+                Ok(mut account_info) => {
                     account_info.access_token = String::from(token);
                     account_info.username = String::from(username);
                     account_info.uuid = String::from(uuid);
@@ -116,8 +116,8 @@ pub async fn get_account_info(
                 },
             };
 
-        file::write_vec(
-            &serde_json::to_vec(&account_info).unwrap(),
+        file::write_value(
+            &account_info,
             format!("launcher/auth/{}.json", { uuid }).as_str(),
         )
         .unwrap();
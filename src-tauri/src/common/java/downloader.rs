@@ -4,7 +4,10 @@ use tauri::{AppHandle, Manager};
 
 use crate::{
     common::utils::file,
-    data::models::{BaseEventPayload, DownloadInstanceEventPayload},
+    data::{
+        constants::ADOPTIUM_API_BASE,
+        models::{BaseEventPayload, DownloadInstanceEventPayload},
+    },
     utils::directory::check_directory,
 };
 
@@ -21,11 +24,15 @@ pub async fn download(
     }
     let path: PathBuf = check_directory(&format!("java/{version}")).await;
 
-    let binary: Value = get_version_info(version).await.unwrap();
+    let binary: Value = get_version_info(version).await?;
 
-    let checksum: &str = binary["package"]["checksum"].as_str().unwrap();
-    let link: &str = binary["package"]["link"].as_str().unwrap();
-    let size: u64 = binary["package"]["size"].as_u64().unwrap();
+    let checksum: &str = binary["package"]["checksum"]
+        .as_str()
+        .ok_or_else(|| format!("Adoptium lists no checksum for Java {version}"))?;
+    let link: &str = binary["package"]["link"]
+        .as_str()
+        .ok_or_else(|| format!("Adoptium lists no download for Java {version}"))?;
+    let size: u64 = binary["package"]["size"].as_u64().unwrap_or_default();
 
     app.emit_all(
         "download",
@@ -45,13 +52,12 @@ pub async fn download(
         link,
         checksum,
         &file::ChecksumType::SHA256,
-        path.to_str().unwrap(),
+        path.to_str().ok_or("The Java folder is not valid unicode")?,
         true,
         false,
-        Some((&app, instance_name)),
+        Some((app, instance_name)),
     )
-    .await
-    .unwrap();
+    .await?;
 
     Ok(())
 }
@@ -68,12 +74,16 @@ async fn get_version_info(version: u8) -> Result<Value, Box<dyn std::error::Erro
     };
 
     let json: Value = file::download_as_json(&format!(
-        "https://api.adoptium.net/v3/assets/feature_releases/{version}/ga?os={os}&architecture={arch}&image_type=jre"
+        "{ADOPTIUM_API_BASE}/v3/assets/feature_releases/{version}/ga?os={os}&architecture={arch}&image_type=jre"
     ), "", &file::ChecksumType::SHA1, "", false, false, None).await?;
 
-    let binaries: &Vec<Value> = json.as_array().unwrap()[0]["binaries"].as_array().unwrap();
+    // an empty list means Adoptium has no build of this version for the os and architecture
+    let binary: &Value = json
+        .as_array()
+        .and_then(|releases| releases.first())
+        .and_then(|release| release["binaries"].as_array())
+        .and_then(|binaries| binaries.first())
+        .ok_or_else(|| format!("Adoptium has no Java {version} runtime for {os} {arch}"))?;
 
-    let binary: Value = binaries[0].clone();
-
-    Ok(binary)
+    Ok(binary.clone())
 }
@@ -4,14 +4,51 @@ use std::{
     path::PathBuf,
 };
 
+// adoptium ships the macOS runtime as a bundle
+#[cfg(target_os = "windows")]
+static JAVA_EXECUTABLE: &str = "bin/javaw.exe";
+#[cfg(target_os = "macos")]
+static JAVA_EXECUTABLE: &str = "Contents/Home/bin/java";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+static JAVA_EXECUTABLE: &str = "bin/java";
+
+/// The runtime used for a game asking for Java `major_version`. Only LTS releases are installed,
+/// a game built for a newer feature release runs on the next LTS. Versions past the newest known
+/// LTS are installed as asked.
+pub fn runtime_version(major_version: u64) -> u8 {
+    match major_version {
+        0..=8 => 8,
+        9..=17 => 17,
+        18..=21 => 21,
+        22..=25 => 25,
+        other => u8::try_from(other).unwrap_or(u8::MAX),
+    }
+}
+
 pub async fn get_java_path(version: u8) -> String {
     let path: PathBuf = check_directory(&format!("java/{version}")).await;
 
     for entry in fs::read_dir(path).unwrap() {
         let entry: DirEntry = entry.unwrap();
-        let path: PathBuf = entry.path().join("bin/javaw.exe");
+        let path: PathBuf = entry.path().join(JAVA_EXECUTABLE);
 
         return String::from(path.to_str().unwrap());
     }
     return String::from("");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn games_run_on_the_next_lts_runtime() {
+        assert_eq!(runtime_version(8), 8);
+        assert_eq!(runtime_version(16), 17);
+        assert_eq!(runtime_version(17), 17);
+        assert_eq!(runtime_version(21), 21);
+        assert_eq!(runtime_version(22), 25);
+        assert_eq!(runtime_version(25), 25);
+        assert_eq!(runtime_version(26), 26);
+    }
+}
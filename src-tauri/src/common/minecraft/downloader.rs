@@ -9,12 +9,15 @@ use crate::{
         file::{library_name_to_raw_path, write_vec},
         log::write_line,
     },
-    data::models::{BaseEventPayload, DownloadInstanceEventPayload},
+    data::{
+        constants::{FORGE_MAVEN_BASE, MINECRAFT_RESOURCES_BASE},
+        models::{BaseEventPayload, DownloadInstanceEventPayload},
+    },
     utils::file,
 };
 use serde_json::{self, Map, Value};
 
-use super::versions::get_version;
+use super::versions::{get_version, parse_version_id};
 
 #[derive(Serialize, Deserialize)]
 struct VersionInfo {
@@ -76,15 +79,7 @@ pub async fn download(
             client_url,
             client_checksum,
             &file::ChecksumType::SHA1,
-            format!(
-                "versions/{}.jar",
-                if version_copy.starts_with("_") {
-                    version_copy[1..].to_string()
-                } else {
-                    version_copy
-                }
-            )
-            .as_str(),
+            format!("versions/{}.jar", parse_version_id(&version_copy).0).as_str(),
             false,
             false,
             Some((&handle_copy, &instance_name_copy)),
@@ -164,49 +159,11 @@ fn compute_total_size(version_info: &Value) -> u64 {
 
     if version_info["libraries"].is_array() {
         for download in version_info["libraries"].as_array().unwrap().clone() {
-            let mut must_download: bool = true;
-            let formatted_os: &str = match OS {
-                "macos" => "osx",
-                _ => OS,
-            };
-            if let Some(rules) = download.get("rules") {
-                for rule in rules.as_array().unwrap().iter() {
-                    if let Some(action) = rule.get("action").and_then(|a| a.as_str()) {
-                        if action == "allow" {
-                            if let Some(os) = rule.get("os") {
-                                if let Some(name) = os.get("name") {
-                                    if name.as_str().unwrap() != formatted_os {
-                                        must_download = false;
-                                    }
-                                }
-                            }
-                        } else if action == "disallow" {
-                            if let Some(os) = rule.get("os") {
-                                if let Some(name) = os.get("name") {
-                                    if name.as_str().unwrap() == formatted_os {
-                                        must_download = false;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            if must_download {
+            if must_download(&download) {
                 if let Some(artifact) = download["downloads"].get("artifact") {
                     total_size += artifact["size"].as_u64().unwrap_or(0);
                 }
-                if let Some(natives_classifier) = download["natives"].get(formatted_os) {
-                    let arch: &str = match ARCH {
-                        "x86" => "32",
-                        "x86_64" => "64",
-                        _ => "64",
-                    };
-                    let natives_classifier: String = natives_classifier
-                        .as_str()
-                        .unwrap()
-                        .replace("${arch}", arch);
-
+                if let Some(natives_classifier) = natives_classifier(&download) {
                     if let Some(natives) =
                         download["downloads"]["classifiers"].get(natives_classifier)
                     {
@@ -219,6 +176,71 @@ fn compute_total_size(version_info: &Value) -> u64 {
     total_size
 }
 
+fn formatted_os() -> &'static str {
+    match OS {
+        "macos" => "osx",
+        _ => OS,
+    }
+}
+
+/// Evaluates the os rules of a library entry.
+pub fn must_download(library: &Value) -> bool {
+    let mut must_download: bool = true;
+    let formatted_os: &str = formatted_os();
+    if let Some(rules) = library.get("rules") {
+        for rule in rules.as_array().unwrap().iter() {
+            if let Some(action) = rule.get("action").and_then(|a| a.as_str()) {
+                if action == "allow" {
+                    if let Some(os) = rule.get("os") {
+                        if let Some(name) = os.get("name") {
+                            if name.as_str().unwrap() != formatted_os {
+                                must_download = false;
+                            }
+                        }
+                    }
+                } else if action == "disallow" {
+                    if let Some(os) = rule.get("os") {
+                        if let Some(name) = os.get("name") {
+                            if name.as_str().unwrap() == formatted_os {
+                                must_download = false;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    must_download
+}
+
+/// Returns the download url of a library artifact, old forge manifests leave it empty.
+pub fn artifact_url(artifact: &Value) -> String {
+    let url: &str = artifact["url"].as_str().unwrap_or_default();
+    let library_path: &str = artifact["path"].as_str().unwrap_or_default();
+    if !url.is_empty() || !library_path.starts_with("net/minecraftforge/forge") {
+        return url.to_string();
+    }
+    if library_path.contains("universal") {
+        format!("{FORGE_MAVEN_BASE}/{library_path}")
+    } else {
+        format!(
+            "{FORGE_MAVEN_BASE}/{}-launcher.jar",
+            library_path.replace(".jar", "")
+        )
+    }
+}
+
+/// Returns the natives classifier of a library for the current platform, if it has one.
+pub fn natives_classifier(library: &Value) -> Option<String> {
+    let natives_classifier: &str = library["natives"].get(formatted_os())?.as_str()?;
+    let arch: &str = match ARCH {
+        "x86" => "32",
+        "x86_64" => "64",
+        _ => "64",
+    };
+    Some(natives_classifier.replace("${arch}", arch))
+}
+
 async fn download_assets(
     url: &str,
     id: &str,
@@ -252,7 +274,7 @@ async fn download_assets(
                 object_url = custom_url.unwrap().to_string();
             } else {
                 object_url = format!(
-                    "https://resources.download.minecraft.net/{}/{}",
+                    "{MINECRAFT_RESOURCES_BASE}/{}/{}",
                     &object_hash[0..2],
                     &object_hash
                 );
@@ -304,40 +326,8 @@ pub async fn download_libraries(
     let mut download_tasks: Vec<async_runtime::JoinHandle<()>> = vec![];
 
     for download in libraries.clone() {
-        let mc_version: String = if version.starts_with("_") {
-            version[1..].to_string()
-        } else {
-            String::from(version)
-        };
-        let mut must_download: bool = true;
-        let formatted_os: &str = match OS {
-            "macos" => "osx",
-            _ => OS,
-        };
-        if let Some(rules) = download.get("rules") {
-            for rule in rules.as_array().unwrap().iter() {
-                if let Some(action) = rule.get("action").and_then(|a| a.as_str()) {
-                    if action == "allow" {
-                        if let Some(os) = rule.get("os") {
-                            if let Some(name) = os.get("name") {
-                                if name.as_str().unwrap() != formatted_os {
-                                    must_download = false;
-                                }
-                            }
-                        }
-                    } else if action == "disallow" {
-                        if let Some(os) = rule.get("os") {
-                            if let Some(name) = os.get("name") {
-                                if name.as_str().unwrap() == formatted_os {
-                                    must_download = false;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        if must_download {
+        let mc_version: String = parse_version_id(version).0.to_string();
+        if must_download(&download) {
             let handle_copy: AppHandle = app.clone();
             let instance_name_copy: String = instance_name.to_string();
             if let Some(artifact) = download["downloads"].get("artifact") {
@@ -346,23 +336,9 @@ pub async fn download_libraries(
                 libraries_arg = format!("{libraries_arg}${{libraries_path}}/{library_path};",);
                 let download_task: async_runtime::JoinHandle<()> =
                     tauri::async_runtime::spawn(async move {
-                        let mut url: String =
-                            artifact["url"].as_str().unwrap_or_default().to_string();
+                        let url: String = artifact_url(&artifact);
                         let hash: &str = artifact["sha1"].as_str().unwrap_or_default();
                         let library_path: &str = artifact["path"].as_str().unwrap_or_default();
-                        if url.is_empty() {
-                            if library_path.starts_with("net/minecraftforge/forge") {
-                                if library_path.contains("universal") {
-                                    url =
-                                        format!("https://maven.minecraftforge.net/{library_path}");
-                                } else {
-                                    url = format!(
-                                        "https://maven.minecraftforge.net/{}-launcher.jar",
-                                        library_path.replace(".jar", "")
-                                    );
-                                }
-                            }
-                        }
                         file::download_as_vec(
                             &url,
                             &hash,
@@ -378,19 +354,10 @@ pub async fn download_libraries(
                 download_tasks.push(download_task);
             }
             let instance_name_copy: String = instance_name.to_string();
-            if let Some(natives_classifier) = download["natives"].get(formatted_os) {
+            if let Some(natives_classifier) = natives_classifier(&download) {
                 if skip_natives {
                     continue;
                 }
-                let arch: &str = match ARCH {
-                    "x86" => "32",
-                    "x86_64" => "64",
-                    _ => "64",
-                };
-                let natives_classifier: String = natives_classifier
-                    .as_str()
-                    .unwrap()
-                    .replace("${arch}", arch);
 
                 if let Some(natives) = download["downloads"]["classifiers"].get(natives_classifier)
                 {
@@ -13,14 +13,19 @@ use std::{
 
 use crate::{
     common::auth::login::get_active_account,
-    common::{modloader, utils::directory::check_directory_sync},
+    common::utils::file::{self},
     common::{
-        modloader::util::get_manifest,
-        utils::file::{self},
+        modloader::loader::{self, LaunchContribution},
+        utils::{
+            directory::{
+                check_directory_sync, get_data_root, is_excluded, relative_files, safe_join,
+            },
+            log::write_line,
+        },
     },
     data::models::{
-        BaseEventPayload, DownloadInstanceEventPayload, InstanceInfo, MinecraftAccount,
-        StartInstanceEventPayload,
+        BaseEventPayload, DownloadInstanceEventPayload, InstanceInfo, InstanceModLoader,
+        MinecraftAccount, StartInstanceEventPayload,
     },
     java::{downloader as javaDownloader, get_java_path::get_java_path},
     minecraft::downloader,
@@ -28,13 +33,20 @@ use crate::{
 };
 
 use tauri::{AppHandle, Manager};
+use tokio::sync::RwLockReadGuard;
 
-use super::downloader::download_libraries;
-
-pub async fn create_instance(id: &str, name: &str, modloader: &str, app: &tauri::AppHandle) {
-    let og_id: String = id.to_string();
-    let mut id: String = id.to_string();
+use super::{
+    downloader::download_libraries,
+    storage::lock_for_install,
+    versions::{java_version, parse_version_id},
+};
 
+pub async fn create_instance(
+    id: &str,
+    name: &str,
+    modloader: &InstanceModLoader,
+    app: &tauri::AppHandle,
+) {
     app.emit_all(
         "download",
         DownloadInstanceEventPayload {
@@ -49,63 +61,8 @@ pub async fn create_instance(id: &str, name: &str, modloader: &str, app: &tauri:
     )
     .unwrap();
 
-    javaDownloader::download(8, app, name).await.unwrap();
-    javaDownloader::download(17, app, name).await.unwrap();
-
-    if modloader.starts_with("forge-") {
-        let forge_manifest: Value = modloader::forge::download_manifest(modloader, app, name)
-            .await
-            .unwrap();
-        id = forge_manifest["inheritsFrom"].as_str().unwrap().to_string();
-    } else if modloader.starts_with("fabric-") {
-        let fabric_manifest: Value = modloader::fabric::download_manifest(&id, modloader)
-            .await
-            .unwrap();
-        id = fabric_manifest["inheritsFrom"]
-            .as_str()
-            .unwrap()
-            .to_string();
-    } else if modloader.starts_with("quilt-") {
-        let quilt_manifest: Value = modloader::quilt::download_manifest(&id, modloader)
-            .await
-            .unwrap();
-        id = quilt_manifest["inheritsFrom"].as_str().unwrap().to_string();
-    }
-
-    downloader::download(&id, app, name).await.unwrap();
-
-    if modloader.starts_with("forge") {
-        modloader::forge::download_forge(&id, &modloader.replace("forge-", ""), name, app)
-            .await
-            .unwrap();
-    } else if modloader.starts_with("fabric") {
-        modloader::fabric::download_fabric(&id, &modloader.replace("fabric-", ""), app, &name)
-            .await
-            .unwrap();
-    } else if modloader.starts_with("quilt") {
-        modloader::quilt::download_quilt(&id, &modloader.replace("quilt-", ""), app, &name)
-            .await
-            .unwrap();
-    }
-
-    let instance_info: InstanceInfo = InstanceInfo {
-        name: String::from(name),
-        version: og_id,
-        background: String::from("default0"),
-        icon: String::from("default0"),
-        width: String::from("1920"),
-        height: String::from("1080"),
-        modloader: String::from(modloader),
-        fullscreen: false,
-    };
-
-    check_directory(format!("instances/{name}/resourcepacks").as_str()).await;
-
-    file::write_vec(
-        &serde_json::to_vec(&instance_info).unwrap(),
-        &format!("instances/{name}/atlas_instance.json"),
-    )
-    .unwrap();
+    let instance_info: InstanceInfo = InstanceInfo::new(name, id, modloader);
+    install_instance(&instance_info, app).await.unwrap();
 
     app.emit_all(
         "download",
@@ -129,23 +86,17 @@ pub async fn launch_instance(name: &str, app: &tauri::AppHandle) {
             .await
             .unwrap();
 
-    let mut modloader_manifest: Option<Value> = None;
+    let modloader_manifest: Option<Value> =
+        loader::resolve_manifest(&instance_info.modloader, &instance_info.version, app, name)
+            .await
+            .unwrap();
 
     let version_info: Value = file::read_as_value(
         format!(
             "launcher/meta/net.minecraft/{}.json",
-            if instance_info.modloader.is_empty() {
-                instance_info.version.to_string()
-            } else {
-                let new_modloader_manifest: Value =
-                    get_manifest(&instance_info.modloader, app, name, &instance_info.version)
-                        .await
-                        .unwrap();
-                modloader_manifest = Some(new_modloader_manifest.clone());
-                new_modloader_manifest["inheritsFrom"]
-                    .as_str()
-                    .unwrap()
-                    .to_string()
+            match &modloader_manifest {
+                Some(modloader_manifest) => loader::inherits_from(modloader_manifest).unwrap(),
+                None => instance_info.version.to_string(),
             }
         )
         .as_str(),
@@ -153,22 +104,32 @@ pub async fn launch_instance(name: &str, app: &tauri::AppHandle) {
     .await
     .unwrap();
 
+    let mut contribution: LaunchContribution = match &modloader_manifest {
+        Some(modloader_manifest) => {
+            loader::launch_contribution(&instance_info.modloader, modloader_manifest)
+        }
+        None => LaunchContribution::default(),
+    };
+    for addon in instance_info.addons.iter() {
+        if let Some(addon_manifest) =
+            loader::resolve_manifest(addon, &instance_info.version, app, name)
+                .await
+                .unwrap()
+        {
+            contribution.merge(loader::launch_contribution(addon, &addon_manifest));
+        }
+    }
+
     // active user
     let active_user: MinecraftAccount = get_active_account().unwrap();
 
     // java
-    let mut java_version: u64 = version_info["javaVersion"]["majorVersion"]
-        .as_u64()
-        .unwrap_or(8);
-
-    java_version = match java_version {
-        8 => 8,
-        16 => 17,
-        17 => 17,
-        _ => 8,
-    };
-
-    let java_path: String = get_java_path(java_version.try_into().unwrap()).await;
+    let java_version: u8 = java_version(&version_info);
+    if let Err(err) = javaDownloader::download(java_version, app, name).await {
+        write_line(&format!("Could not install Java {java_version} for {name}: {err}"));
+        return;
+    }
+    let java_path: String = get_java_path(java_version).await;
 
     // libraries
     let libraries_path: String = String::from(check_directory("libraries").await.to_str().unwrap());
@@ -185,37 +146,35 @@ pub async fn launch_instance(name: &str, app: &tauri::AppHandle) {
         None => String::from(""),
     };
 
-    let forge_jar = check_directory(format!("versions").as_str())
-        .await
-        .join(format!("{}.jar", { &instance_info.modloader }));
+    let version_jar: Option<PathBuf> = contribution
+        .version_jar
+        .as_ref()
+        .map(|version_jar| get_data_root().join(version_jar))
+        .filter(|version_jar| version_jar.exists());
 
     // classpath
-    let version_path: String = if instance_info.modloader.is_empty() || !forge_jar.exists() {
-        String::from(
+    let version_path: String = match version_jar {
+        Some(version_jar) => String::from(version_jar.to_str().unwrap()),
+        None => String::from(
             check_directory(format!("versions").as_str())
                 .await
                 .join(format!("{}.jar", { &instance_info.version }))
                 .to_str()
                 .unwrap(),
-        )
-    } else {
-        String::from(
-            check_directory(format!("versions").as_str())
-                .await
-                .join(format!("{}.jar", { &instance_info.modloader }))
-                .to_str()
-                .unwrap(),
-        )
+        ),
     };
     let mut cp: String = format!("{version_path};{libraries}",);
 
     // args
-    let instance_path: String = String::from(
-        check_directory(format!("instances/{name}").as_str())
-            .await
-            .to_str()
-            .unwrap(),
-    );
+    let instance_path: String = match &instance_info.game_dir {
+        Some(game_dir) => game_dir.to_string(),
+        None => String::from(
+            check_directory(format!("instances/{name}").as_str())
+                .await
+                .to_str()
+                .unwrap(),
+        ),
+    };
 
     let version_type: &str = version_info["type"].as_str().unwrap();
 
@@ -330,7 +289,7 @@ pub async fn launch_instance(name: &str, app: &tauri::AppHandle) {
         String::from("-XX:G1ReservePercent=20"),
     ]);
 
-    if java_version == 17 {
+    if java_version >= 17 {
         parsed_jvm_arguments.append(&mut vec![
             String::from("-XX:+UnlockDiagnosticVMOptions"),
             String::from("-XX:+AlwaysActAsServerClassMachine"),
@@ -374,42 +333,33 @@ pub async fn launch_instance(name: &str, app: &tauri::AppHandle) {
             String::from("-XX:G1HeapRegionSize=32M"),
         ]);
     }
+    parsed_jvm_arguments.extend(instance_info.jvm_args.iter().cloned());
 
     let mut main_class: String = version_info["mainClass"].as_str().unwrap().to_string();
 
-    if modloader_manifest.is_some() {
-        let modloader_manifest: Value = modloader_manifest.unwrap();
-        if let Some(modloader_arguments) = modloader_manifest["arguments"]["game"].as_array() {
-            for argument in modloader_arguments {
-                parsed_game_arguments.push(argument.as_str().unwrap().to_string());
-            }
-        }
-        if let Some(modloader_jvm_argument) = modloader_manifest["arguments"]["jvm"].as_array() {
-            for argument in modloader_jvm_argument {
-                parsed_jvm_arguments.push(argument.as_str().unwrap().to_string());
-            }
-        }
-        if let Some(libraries) = modloader_manifest["libraries"].as_array() {
-            let modloader_libraries: String = download_libraries(
-                libraries,
-                &instance_info.version,
-                true,
-                app,
-                &instance_info.name,
-            )
-            .await
-            .unwrap();
-            cp = format!("{cp};{modloader_libraries}");
-        }
-
-        if let Some(mc) = modloader_manifest["mainClass"].as_str() {
-            main_class = mc.to_string();
-        }
-        if let Some(cp_ignore) = modloader_manifest["arguments"]["cp_ignore"].as_array() {
-            for ignore in cp_ignore {
-                cp = cp.replace(ignore.as_str().unwrap(), "");
-            }
-        }
+    parsed_game_arguments.extend(contribution.game_arguments);
+    // every loader may bring tweakers, they only work in the right order
+    let mut tweak_classes: Vec<String> = loader::take_tweak_classes(&mut parsed_game_arguments);
+    tweak_classes.extend(contribution.tweak_classes);
+    parsed_game_arguments.extend(loader::tweak_arguments(tweak_classes));
+    parsed_jvm_arguments.extend(contribution.jvm_arguments);
+    if !contribution.libraries.is_empty() {
+        let modloader_libraries: String = download_libraries(
+            &contribution.libraries,
+            &instance_info.version,
+            true,
+            app,
+            &instance_info.name,
+        )
+        .await
+        .unwrap();
+        cp = format!("{cp};{modloader_libraries}");
+    }
+    if let Some(mc) = contribution.main_class {
+        main_class = mc;
+    }
+    for ignore in contribution.classpath_ignore.iter() {
+        cp = cp.replace(ignore, "");
     }
 
     if OS == "windows" {
@@ -582,9 +532,19 @@ pub async fn get_instances() -> Vec<InstanceInfo> {
     for entry in fs::read_dir(instances_path).unwrap() {
         let entry: DirEntry = entry.unwrap();
         let path: PathBuf = entry.path();
+        let atlas_instance_path: PathBuf = path.join("atlas_instance.json");
 
-        let contents: String = fs::read_to_string(&path.join("atlas_instance.json")).unwrap();
-        let mut instance: InstanceInfo = serde_json::from_str(&contents).unwrap();
+        let contents: String = match fs::read_to_string(&atlas_instance_path) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+        let mut instance: InstanceInfo = match serde_json::from_str(&contents) {
+            Ok(instance) => instance,
+            Err(_) => {
+                file::quarantine(&atlas_instance_path);
+                continue;
+            }
+        };
         if !instance.background.starts_with("default") {
             instance.background = path.join(instance.background).to_str().unwrap().to_string();
         }
@@ -611,6 +571,186 @@ pub fn open_folder(name: &str) {
     Command::new("explorer").arg(path).spawn().unwrap();
 }
 
+/// Copies an instance under a new name, with its game files but the `exclude`d ones. Libraries
+/// and assets are shared by every instance, so nothing is downloaded again.
+pub async fn clone_instance(
+    name: &str,
+    new_name: &str,
+    exclude: &[String],
+) -> Result<InstanceInfo, Box<dyn std::error::Error>> {
+    let mut instance: InstanceInfo =
+        file::read_as_value(&format!("instances/{name}/atlas_instance.json")).await?;
+    let instances_path: PathBuf = check_directory("instances").await;
+    let instance_path: PathBuf = instances_path.join(name);
+    let new_instance_path: PathBuf = safe_join(&instances_path, new_name)?;
+    if new_name.trim().is_empty() || new_instance_path.exists() {
+        return Err(format!("An instance named {new_name} already exists").into());
+    }
+
+    // the images keep their names, so the copy looks like the original
+    let mut files: Vec<(PathBuf, String)> = vec![];
+    for image in [&instance.background, &instance.icon] {
+        if !image.starts_with("default") {
+            files.push((instance_path.join(image), image.to_string()));
+        }
+    }
+    let game_path: PathBuf = game_path(&instance);
+    for path in relative_files(&game_path) {
+        if path != "atlas_instance.json" && !is_excluded(&path, exclude) {
+            files.push((game_path.join(&path), path));
+        }
+    }
+
+    instance.name = new_name.to_string();
+    instance.game_dir = None;
+    if let Err(err) = copy_files(&files, &new_instance_path) {
+        let _ = fs::remove_dir_all(&new_instance_path);
+        return Err(err);
+    }
+    file::write_value(&instance, &format!("instances/{new_name}/atlas_instance.json"))?;
+    Ok(instance)
+}
+
+fn copy_files(
+    files: &[(PathBuf, String)],
+    target: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for (source, path) in files {
+        let destination: PathBuf = safe_join(target, path)?;
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(source, destination)?;
+    }
+    Ok(())
+}
+
+/// Where the game files of an instance live, its own folder unless it is linked to another one.
+pub fn game_path(instance: &InstanceInfo) -> PathBuf {
+    match &instance.game_dir {
+        Some(game_dir) => PathBuf::from(game_dir),
+        None => get_data_root().join(format!("instances/{}", instance.name)),
+    }
+}
+
+/// Replaces the addon loaders of an instance, the new ones are installed first.
+pub async fn set_addons(
+    name: &str,
+    addons: Vec<InstanceModLoader>,
+    app: &AppHandle,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _install: RwLockReadGuard<'static, ()> = lock_for_install().await;
+    let instance_path: String = format!("instances/{name}/atlas_instance.json");
+    let mut instance: InstanceInfo = file::read_as_value(&instance_path).await?;
+    let game_version: &str = parse_version_id(&instance.version).0;
+
+    for addon in addons.iter() {
+        if !addon.is_addon() {
+            return Err(format!("{addon} can't be stacked on another loader").into());
+        }
+        let manifest: Value = loader::resolve_manifest(addon, &instance.version, app, name)
+            .await?
+            .ok_or("The addon has no manifest")?;
+        let id: String = loader::inherits_from(&manifest)?;
+        if parse_version_id(&id).0 != game_version {
+            return Err(format!("{addon} is not built for {}", instance.version).into());
+        }
+        loader::install(addon, &id, app, name).await?;
+    }
+
+    instance.addons = addons;
+    file::write_value(&instance, &instance_path)?;
+    Ok(())
+}
+
+/// Moves an instance to another game and/or loader version. Everything the new version needs is
+/// installed before the instance config is swapped, so when a step fails the instance keeps its
+/// previous configuration and still launches as before.
+pub async fn change_version(
+    name: &str,
+    version: &str,
+    modloader: &InstanceModLoader,
+    app: &AppHandle,
+) -> Result<InstanceInfo, Box<dyn std::error::Error>> {
+    let _install: RwLockReadGuard<'static, ()> = lock_for_install().await;
+    let instance_path: String = format!("instances/{name}/atlas_instance.json");
+    let previous: InstanceInfo = file::read_as_value(&instance_path).await?;
+    if previous.version == version && previous.modloader == *modloader {
+        return Ok(previous);
+    }
+
+    let mut instance: InstanceInfo = previous.clone();
+    instance.version = version.to_string();
+    instance.modloader = modloader.clone();
+    if previous.version != version || previous.modloader.loader_type() != modloader.loader_type()
+    {
+        // addons are built for a single game version and only stack on the loader they were
+        // picked for, a Forge build of OptiFine won't launch on Fabric
+        instance.addons.clear();
+    }
+
+    if let Err(err) = install_version(name, &instance, app).await {
+        write_line(&format!(
+            "Failed to move {name} to {} {}, keeping {} {}: {err}",
+            instance.version, instance.modloader, previous.version, previous.modloader
+        ));
+        app.emit_all(
+            "download",
+            DownloadInstanceEventPayload {
+                base: BaseEventPayload {
+                    message: format!("{err}"),
+                    status: String::from("Error"),
+                },
+                total: 0,
+                downloaded: 0,
+                name: name.to_string(),
+            },
+        )?;
+        return Err(err);
+    }
+
+    file::write_value(&instance, &instance_path)?;
+    Ok(instance)
+}
+
+/// Installs the game and loaders of a new instance and writes its config, the instance only shows
+/// up once everything it needs is on disk.
+pub async fn install_instance(
+    instance: &InstanceInfo,
+    app: &AppHandle,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _install: RwLockReadGuard<'static, ()> = lock_for_install().await;
+    let name: &str = &instance.name;
+    install_version(name, instance, app).await?;
+
+    check_directory(&format!("instances/{name}/resourcepacks")).await;
+    file::write_value(instance, &format!("instances/{name}/atlas_instance.json"))
+}
+
+async fn install_version(
+    name: &str,
+    instance: &InstanceInfo,
+    app: &AppHandle,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut id: String = instance.version.clone();
+    if let Some(manifest) = loader::resolve_manifest(&instance.modloader, &id, app, name).await? {
+        id = loader::inherits_from(&manifest)?;
+    }
+
+    downloader::download(&id, app, name).await?;
+
+    let version_info: Value =
+        file::read_as_value(&format!("launcher/meta/net.minecraft/{id}.json")).await?;
+    javaDownloader::download(java_version(&version_info), app, name).await?;
+
+    loader::install(&instance.modloader, &id, app, name).await?;
+    for addon in instance.addons.iter() {
+        loader::install(addon, &id, app, name).await?;
+    }
+
+    Ok(())
+}
+
 pub async fn write_instance(name: &str, data: InstanceInfo, app: &AppHandle) {
     let instances_path: PathBuf = check_directory_sync(format!("instances").as_str());
     let old_instance_path: PathBuf = instances_path.join(name);
@@ -681,57 +821,17 @@ pub async fn write_instance(name: &str, data: InstanceInfo, app: &AppHandle) {
         instance.icon = data.icon.to_string();
     }
 
+    let mut version_changed: bool = true;
     if instance.version != data.version || instance.modloader != data.modloader {
-        let mut id: String = data.version.clone();
-        if data.modloader.starts_with("forge-") {
-            let forge_manifest: Value =
-                modloader::forge::download_manifest(&data.modloader, app, name)
-                    .await
-                    .unwrap();
-            id = forge_manifest["inheritsFrom"].as_str().unwrap().to_string();
-        } else if data.modloader.starts_with("fabric-") {
-            let fabric_manifest: Value = modloader::fabric::download_manifest(&id, &data.modloader)
-                .await
-                .unwrap();
-            id = fabric_manifest["inheritsFrom"]
-                .as_str()
-                .unwrap()
-                .to_string();
-        } else if data.modloader.starts_with("quilt-") {
-            let quilt_manifest: Value = modloader::quilt::download_manifest(&id, &data.modloader)
-                .await
-                .unwrap();
-            id = quilt_manifest["inheritsFrom"].as_str().unwrap().to_string();
-        }
-
-        downloader::download(&id, app, name).await.unwrap();
-
-        if data.modloader.starts_with("forge") {
-            modloader::forge::download_forge(&id, &data.modloader.replace("forge-", ""), name, app)
-                .await
-                .unwrap();
-        } else if data.modloader.starts_with("fabric") {
-            modloader::fabric::download_fabric(
-                &id,
-                &data.modloader.replace("fabric-", ""),
-                app,
-                &name,
-            )
-            .await
-            .unwrap();
-        } else if data.modloader.starts_with("quilt") {
-            modloader::quilt::download_quilt(
-                &id,
-                &data.modloader.replace("quilt-", ""),
-                app,
-                &name,
-            )
-            .await
-            .unwrap();
+        // a failed change is already reported, the other settings are still saved
+        match change_version(&data.name, &data.version, &data.modloader, app).await {
+            Ok(changed) => {
+                instance.version = changed.version;
+                instance.modloader = changed.modloader;
+                instance.addons = changed.addons;
+            }
+            Err(_) => version_changed = false,
         }
-
-        instance.version = data.version;
-        instance.modloader = data.modloader;
     }
 
     instance.height = data.height;
@@ -744,6 +844,10 @@ pub async fn write_instance(name: &str, data: InstanceInfo, app: &AppHandle) {
     )
     .unwrap();
 
+    if !version_changed {
+        return;
+    }
+
     app.emit_all(
         "download",
         DownloadInstanceEventPayload {
@@ -1,3 +1,6 @@
 pub mod versions;
 pub mod downloader;
-pub mod instance;
\ No newline at end of file
+pub mod instance;
+pub mod mods;
+pub mod repair;
+pub mod storage;
\ No newline at end of file
@@ -0,0 +1,559 @@
+use std::{
+    fs,
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose, Engine};
+use serde_json::Value;
+use zip::{read::ZipFile, ZipArchive};
+
+use crate::{
+    common::utils::file::read_as_value,
+    data::models::{InstalledMod, InstanceInfo, ModDependency, ModMetadataFormat},
+};
+
+use super::instance::game_path;
+
+static DISABLED_SUFFIX: &str = ".disabled";
+
+/// What a jar declares about itself, the icon is still a path inside the jar.
+#[derive(Default)]
+struct ModMetadata {
+    format: Option<ModMetadataFormat>,
+    id: String,
+    name: String,
+    version: String,
+    description: String,
+    authors: Vec<String>,
+    dependencies: Vec<ModDependency>,
+    icon: Option<String>,
+}
+
+fn text(value: &Value) -> String {
+    value.as_str().unwrap_or_default().trim().to_string()
+}
+
+/// Authors as a list of names or `{ "name": .. }` objects, a `{ name: role }` map or a comma
+/// separated string.
+fn people(value: &Value) -> Vec<String> {
+    let names: Vec<String> = match value {
+        Value::Array(people) => people
+            .iter()
+            .map(|person| match person {
+                Value::Object(_) => text(&person["name"]),
+                _ => text(person),
+            })
+            .collect(),
+        Value::Object(people) => people.keys().map(|name| name.to_string()).collect(),
+        Value::String(people) => people
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .collect(),
+        _ => vec![],
+    };
+    names.into_iter().filter(|name| !name.is_empty()).collect()
+}
+
+/// A version requirement as a string or a list of alternatives, `*` means any version.
+fn version_range(value: &Value) -> String {
+    let range: String = match value {
+        Value::Array(ranges) => ranges
+            .iter()
+            .map(text)
+            .collect::<Vec<String>>()
+            .join(" || "),
+        _ => text(value),
+    };
+    if range == "*" {
+        String::new()
+    } else {
+        range
+    }
+}
+
+/// An icon path, or the biggest of a `{ size: path }` map.
+fn icon_path(value: &Value) -> Option<String> {
+    let icon: String = match value {
+        Value::Object(icons) => icons
+            .iter()
+            .max_by_key(|(size, _)| size.parse::<u32>().unwrap_or_default())
+            .map(|(_, path)| text(path))?,
+        _ => text(value),
+    };
+    Some(icon).filter(|icon| !icon.is_empty())
+}
+
+fn read_fabric(contents: &[u8]) -> Result<ModMetadata, Box<dyn std::error::Error>> {
+    let manifest: Value = serde_json::from_slice(contents)?;
+
+    let mut dependencies: Vec<ModDependency> = vec![];
+    for (key, required) in [
+        ("depends", true),
+        ("recommends", false),
+        ("suggests", false),
+    ] {
+        if let Some(depends) = manifest[key].as_object() {
+            dependencies.extend(depends.iter().map(|(id, version)| ModDependency {
+                id: id.to_string(),
+                version: version_range(version),
+                required,
+            }));
+        }
+    }
+
+    Ok(ModMetadata {
+        format: Some(ModMetadataFormat::Fabric),
+        id: text(&manifest["id"]),
+        name: text(&manifest["name"]),
+        version: text(&manifest["version"]),
+        description: text(&manifest["description"]),
+        authors: people(&manifest["authors"]),
+        dependencies,
+        icon: icon_path(&manifest["icon"]),
+    })
+}
+
+fn read_quilt(contents: &[u8]) -> Result<ModMetadata, Box<dyn std::error::Error>> {
+    let manifest: Value = serde_json::from_slice(contents)?;
+    let loader: &Value = &manifest["quilt_loader"];
+    let metadata: &Value = &loader["metadata"];
+
+    let dependencies: Vec<ModDependency> = loader["depends"]
+        .as_array()
+        .unwrap_or(&vec![])
+        .iter()
+        .map(|dependency| match dependency {
+            Value::Object(_) => ModDependency {
+                id: text(&dependency["id"]),
+                version: version_range(&dependency["versions"]),
+                required: !dependency["optional"].as_bool().unwrap_or_default(),
+            },
+            _ => ModDependency {
+                id: text(dependency),
+                version: String::new(),
+                required: true,
+            },
+        })
+        .collect();
+
+    Ok(ModMetadata {
+        format: Some(ModMetadataFormat::Quilt),
+        id: text(&loader["id"]),
+        name: text(&metadata["name"]),
+        version: text(&loader["version"]),
+        description: text(&metadata["description"]),
+        authors: people(&metadata["contributors"]),
+        dependencies,
+        icon: icon_path(&metadata["icon"]),
+    })
+}
+
+fn read_mods_toml(
+    contents: &[u8],
+    format: ModMetadataFormat,
+    jar_version: &str,
+) -> Result<ModMetadata, Box<dyn std::error::Error>> {
+    let manifest: Value = serde_json::to_value(toml::from_str::<toml::Value>(
+        std::str::from_utf8(contents)?,
+    )?)?;
+    let entry: &Value = manifest["mods"]
+        .as_array()
+        .and_then(|mods| mods.first())
+        .ok_or("The mods.toml declares no mod")?;
+    let id: String = text(&entry["modId"]);
+
+    let mut dependencies: Vec<ModDependency> = vec![];
+    for dependency in manifest["dependencies"][&id].as_array().unwrap_or(&vec![]) {
+        // Forge has `mandatory`, NeoForge a `type`
+        let required: bool = match (
+            dependency["mandatory"].as_bool(),
+            text(&dependency["type"]).to_lowercase().as_str(),
+        ) {
+            (Some(mandatory), _) => mandatory,
+            (None, "" | "required") => true,
+            (None, "optional") => false,
+            // incompatible and discouraged mods aren't dependencies
+            _ => continue,
+        };
+        dependencies.push(ModDependency {
+            id: text(&dependency["modId"]),
+            version: version_range(&dependency["versionRange"]),
+            required,
+        });
+    }
+
+    Ok(ModMetadata {
+        format: Some(format),
+        name: text(&entry["displayName"]),
+        version: text(&entry["version"]).replace("${file.jarVersion}", jar_version),
+        description: text(&entry["description"]),
+        authors: people(&entry["authors"]),
+        dependencies,
+        icon: icon_path(&entry["logoFile"]).or(icon_path(&manifest["logoFile"])),
+        id,
+    })
+}
+
+/// `modid@[1.0,)` -> (`modid`, `[1.0,)`).
+fn split_dependency(dependency: &str) -> ModDependency {
+    let (id, version) = dependency.split_once('@').unwrap_or((dependency, ""));
+    ModDependency {
+        id: id.trim().to_string(),
+        version: version.trim().to_string(),
+        required: true,
+    }
+}
+
+fn read_mcmod_info(contents: &[u8]) -> Result<ModMetadata, Box<dyn std::error::Error>> {
+    let info: Value = serde_json::from_slice(contents)?;
+    // version 2 wraps the list in `modList`
+    let entry: &Value = match &info {
+        Value::Array(mods) => mods.first(),
+        _ => info["modList"].as_array().and_then(|mods| mods.first()),
+    }
+    .ok_or("The mcmod.info declares no mod")?;
+
+    let mut dependencies: Vec<ModDependency> = entry["requiredMods"]
+        .as_array()
+        .unwrap_or(&vec![])
+        .iter()
+        .map(|dependency| split_dependency(&text(dependency)))
+        .collect();
+    // `dependencies` only orders the loading, what isn't required is optional
+    for dependency in entry["dependencies"].as_array().unwrap_or(&vec![]) {
+        let dependency: ModDependency = split_dependency(&text(dependency));
+        if !dependencies
+            .iter()
+            .any(|required| required.id == dependency.id)
+        {
+            dependencies.push(ModDependency {
+                required: false,
+                ..dependency
+            });
+        }
+    }
+
+    let authors: &Value = if entry["authorList"].is_array() {
+        &entry["authorList"]
+    } else {
+        &entry["authors"]
+    };
+    Ok(ModMetadata {
+        format: Some(ModMetadataFormat::LegacyForge),
+        id: text(&entry["modid"]),
+        name: text(&entry["name"]),
+        version: text(&entry["version"]),
+        description: text(&entry["description"]),
+        authors: people(authors),
+        dependencies,
+        icon: icon_path(&entry["logoFile"]),
+    })
+}
+
+/// The `Implementation-Version` of the jar manifest, what `${file.jarVersion}` stands for.
+fn jar_version(manifest: &[u8]) -> String {
+    String::from_utf8_lossy(manifest)
+        .lines()
+        .find_map(|line| line.strip_prefix("Implementation-Version:"))
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+fn read_entry(jar: &mut ZipArchive<Cursor<Vec<u8>>>, path: &str) -> Option<Vec<u8>> {
+    let mut entry: ZipFile = jar.by_name(path).ok()?;
+    let mut contents: Vec<u8> = vec![];
+    entry.read_to_end(&mut contents).ok()?;
+    Some(contents)
+}
+
+/// Reads the first metadata file the jar has, a jar without any gives empty metadata.
+fn read_metadata(jar: &mut ZipArchive<Cursor<Vec<u8>>>) -> ModMetadata {
+    if let Some(contents) = read_entry(jar, "quilt.mod.json") {
+        if let Ok(metadata) = read_quilt(&contents) {
+            return metadata;
+        }
+    }
+    if let Some(contents) = read_entry(jar, "fabric.mod.json") {
+        if let Ok(metadata) = read_fabric(&contents) {
+            return metadata;
+        }
+    }
+
+    let version: String = match read_entry(jar, "META-INF/MANIFEST.MF") {
+        Some(manifest) => jar_version(&manifest),
+        None => String::new(),
+    };
+    for (path, format) in [
+        ("META-INF/neoforge.mods.toml", ModMetadataFormat::NeoForge),
+        ("META-INF/mods.toml", ModMetadataFormat::Forge),
+    ] {
+        if let Some(contents) = read_entry(jar, path) {
+            if let Ok(metadata) = read_mods_toml(&contents, format, &version) {
+                return metadata;
+            }
+        }
+    }
+
+    if let Some(contents) = read_entry(jar, "mcmod.info") {
+        if let Ok(metadata) = read_mcmod_info(&contents) {
+            return metadata;
+        }
+    }
+    ModMetadata::default()
+}
+
+fn data_url(path: &str, image: &[u8]) -> String {
+    let mime: &str = match Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        _ => "image/png",
+    };
+    format!(
+        "data:{mime};base64,{}",
+        general_purpose::STANDARD.encode(image)
+    )
+}
+
+fn read_mod(path: &Path) -> Result<InstalledMod, Box<dyn std::error::Error>> {
+    let file_name: &str = path
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .ok_or("Invalid mod file name")?;
+    let enabled: bool = !file_name.ends_with(DISABLED_SUFFIX);
+    let file_name: &str = file_name.strip_suffix(DISABLED_SUFFIX).unwrap_or(file_name);
+
+    let mut jar: ZipArchive<Cursor<Vec<u8>>> = ZipArchive::new(Cursor::new(fs::read(path)?))?;
+    let metadata: ModMetadata = read_metadata(&mut jar);
+    let icon: Option<String> = metadata.icon.as_ref().and_then(|icon_path| {
+        read_entry(&mut jar, icon_path.trim_start_matches('/'))
+            .map(|image| data_url(icon_path, &image))
+    });
+
+    Ok(InstalledMod {
+        file_name: file_name.to_string(),
+        enabled,
+        format: metadata.format,
+        id: metadata.id,
+        name: metadata.name,
+        version: metadata.version,
+        description: metadata.description,
+        authors: metadata.authors,
+        dependencies: metadata.dependencies,
+        icon,
+    })
+}
+
+fn is_mod_file(file_name: &str) -> bool {
+    let file_name: &str = file_name.strip_suffix(DISABLED_SUFFIX).unwrap_or(file_name);
+    file_name.ends_with(".jar") || file_name.ends_with(".zip")
+}
+
+async fn mods_path(instance_name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let instance: InstanceInfo =
+        read_as_value(&format!("instances/{instance_name}/atlas_instance.json")).await?;
+    Ok(game_path(&instance).join("mods"))
+}
+
+/// Finds `file_name` in the mods folder, enabled or not, as (enabled path, disabled path).
+fn mod_paths(
+    mods_path: &Path,
+    file_name: &str,
+) -> Result<(PathBuf, PathBuf), Box<dyn std::error::Error>> {
+    let file_name: &str = file_name.strip_suffix(DISABLED_SUFFIX).unwrap_or(file_name);
+    if file_name.is_empty() || file_name.contains(['/', '\\']) || file_name == ".." {
+        return Err(format!("Invalid mod file name {file_name}").into());
+    }
+    Ok((
+        mods_path.join(file_name),
+        mods_path.join(format!("{file_name}{DISABLED_SUFFIX}")),
+    ))
+}
+
+/// The jars in the mods folder of an instance with their metadata, disabled ones included.
+pub async fn list_mods(
+    instance_name: &str,
+) -> Result<Vec<InstalledMod>, Box<dyn std::error::Error>> {
+    let mods_path: PathBuf = mods_path(instance_name).await?;
+    if !mods_path.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut mods: Vec<InstalledMod> = vec![];
+    for entry in fs::read_dir(&mods_path)?.flatten() {
+        let path: PathBuf = entry.path();
+        let file_name: &str = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .unwrap_or_default();
+        if !path.is_file() || !is_mod_file(file_name) {
+            continue;
+        }
+        // a broken jar is still listed, so it can be disabled
+        match read_mod(&path).ok() {
+            Some(installed) => mods.push(installed),
+            None => mods.push(InstalledMod {
+                file_name: file_name
+                    .strip_suffix(DISABLED_SUFFIX)
+                    .unwrap_or(file_name)
+                    .to_string(),
+                enabled: !file_name.ends_with(DISABLED_SUFFIX),
+                format: None,
+                id: String::new(),
+                name: String::new(),
+                version: String::new(),
+                description: String::new(),
+                authors: vec![],
+                dependencies: vec![],
+                icon: None,
+            }),
+        }
+    }
+
+    let sort_name = |installed: &InstalledMod| -> String {
+        if installed.name.is_empty() {
+            installed.file_name.to_lowercase()
+        } else {
+            installed.name.to_lowercase()
+        }
+    };
+    mods.sort_by_key(sort_name);
+    Ok(mods)
+}
+
+/// Reads the metadata of a single mod of an instance, `file_name` may be enabled or not.
+pub async fn inspect_mod(
+    instance_name: &str,
+    file_name: &str,
+) -> Result<InstalledMod, Box<dyn std::error::Error>> {
+    let (enabled_path, disabled_path) = mod_paths(&mods_path(instance_name).await?, file_name)?;
+    let path: PathBuf = if enabled_path.is_file() {
+        enabled_path
+    } else {
+        disabled_path
+    };
+    read_mod(&path)
+}
+
+/// Enables or disables a mod by dropping or adding `.disabled` to its file name.
+pub async fn set_mod_enabled(
+    instance_name: &str,
+    file_name: &str,
+    enabled: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (enabled_path, disabled_path) = mod_paths(&mods_path(instance_name).await?, file_name)?;
+    let (from, to) = if enabled {
+        (disabled_path, enabled_path)
+    } else {
+        (enabled_path, disabled_path)
+    };
+
+    if to.is_file() {
+        if from.is_file() {
+            return Err(format!("Both {} and {} exist", from.display(), to.display()).into());
+        }
+        return Ok(());
+    }
+    if !from.is_file() {
+        return Err(format!("There is no mod named {file_name}").into());
+    }
+    fs::rename(from, to)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    #[test]
+    fn reads_mods_toml() {
+        let contents: &str = r#"
+modLoader = "javafml" # the loader
+loaderVersion = "[47,)"
+license = "MIT"
+logoFile = "logo.png"
+
+[[mods]]
+modId = "examplemod"
+version = "${file.jarVersion}"
+displayName = "Example Mod"
+authors = "Alice, Bob"
+description = '''
+An example mod.
+'''
+
+[[dependencies.examplemod]]
+    modId = "forge"
+    mandatory = true
+    versionRange = "[47,)"
+    ordering = "NONE"
+    side = "BOTH"
+
+[[dependencies.examplemod]]
+    modId = "jei"
+    type = "optional"
+    versionRange = "*"
+"#;
+        let metadata: ModMetadata =
+            read_mods_toml(contents.as_bytes(), ModMetadataFormat::Forge, "1.2.3").unwrap();
+
+        assert_eq!(metadata.id, "examplemod");
+        assert_eq!(metadata.name, "Example Mod");
+        assert_eq!(metadata.version, "1.2.3");
+        assert_eq!(metadata.description, "An example mod.");
+        assert_eq!(metadata.authors, vec!["Alice", "Bob"]);
+        assert_eq!(metadata.icon.as_deref(), Some("logo.png"));
+        assert_eq!(
+            metadata.dependencies,
+            vec![
+                ModDependency {
+                    id: String::from("forge"),
+                    version: String::from("[47,)"),
+                    required: true,
+                },
+                ModDependency {
+                    id: String::from("jei"),
+                    version: String::new(),
+                    required: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_the_metadata_of_a_jar() {
+        let mut writer: ZipWriter<Cursor<Vec<u8>>> = ZipWriter::new(Cursor::new(vec![]));
+        for (name, contents) in [
+            (
+                "META-INF/MANIFEST.MF",
+                "Manifest-Version: 1.0\r\nImplementation-Version: 2.0.1\r\n",
+            ),
+            (
+                "META-INF/neoforge.mods.toml",
+                "logoFile = \"logo.png\"\n[[mods]]\nmodId = \"neomod\"\nversion = \"${file.jarVersion}\"\n",
+            ),
+            ("logo.png", "png"),
+        ] {
+            writer.start_file(name, FileOptions::default()).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        let mut jar: ZipArchive<Cursor<Vec<u8>>> =
+            ZipArchive::new(Cursor::new(writer.finish().unwrap().into_inner())).unwrap();
+
+        let metadata: ModMetadata = read_metadata(&mut jar);
+        assert!(matches!(metadata.format, Some(ModMetadataFormat::NeoForge)));
+        assert_eq!(metadata.id, "neomod");
+        assert_eq!(metadata.version, "2.0.1");
+        assert_eq!(metadata.icon.as_deref(), Some("logo.png"));
+        assert_eq!(read_entry(&mut jar, "logo.png"), Some(b"png".to_vec()));
+        assert_eq!(read_entry(&mut jar, "missing.png"), None);
+    }
+}
@@ -0,0 +1,491 @@
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use futures::{stream, StreamExt};
+use rayon::prelude::*;
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use tauri::{async_runtime, AppHandle};
+use tokio::sync::RwLockReadGuard;
+use zip::{read::ZipFile, ZipArchive};
+
+use crate::{
+    common::{
+        modloader::{forge, loader, neoforge},
+        utils::{
+            directory::{get_data_root, safe_join},
+            events::{emit_progress, emit_status},
+            file::{self, extract_zip, library_name_to_raw_path, read_as_value, ChecksumType},
+            log::write_line,
+        },
+    },
+    data::{
+        constants::MINECRAFT_RESOURCES_BASE,
+        models::{InstanceInfo, InstanceModLoader, RepairReport},
+    },
+};
+
+use super::{
+    downloader::{artifact_url, must_download, natives_classifier},
+    storage::lock_for_install,
+    versions::{get_version, parse_version_id},
+};
+
+struct RepairTarget {
+    path: String,
+    url: String,
+    sha1: String,
+}
+
+/// A natives jar, extracted into the natives folder of the game version instead of being kept.
+#[derive(PartialEq)]
+struct NativesTarget {
+    folder: String,
+    url: String,
+    sha1: String,
+}
+
+impl RepairTarget {
+    fn new(path: String, url: &str, sha1: &str) -> Self {
+        RepairTarget {
+            path,
+            url: url.to_string(),
+            sha1: sha1.to_string(),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        let path: PathBuf = get_data_root().join(&self.path);
+        if self.sha1.is_empty() {
+            return path.is_file();
+        }
+        match fs::read(path) {
+            Ok(bytes) => format!("{:x}", Sha1::digest(&bytes)) == self.sha1,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Verifies every file an instance needs and downloads again the missing or corrupt ones.
+pub async fn repair_instance(
+    name: &str,
+    app: &AppHandle,
+) -> Result<RepairReport, Box<dyn std::error::Error>> {
+    let _install: RwLockReadGuard<'static, ()> = lock_for_install().await;
+    let instance_info: InstanceInfo =
+        read_as_value(&format!("instances/{name}/atlas_instance.json")).await?;
+
+    emit_progress(app, name, "Verifying game files");
+
+    let modloader_manifest: Option<Value> =
+        loader::resolve_manifest(&instance_info.modloader, &instance_info.version, app, name)
+            .await?;
+    let id: String = match &modloader_manifest {
+        Some(manifest) => loader::inherits_from(manifest)?,
+        None => instance_info.version.clone(),
+    };
+
+    let version_path: String = format!("launcher/meta/net.minecraft/{id}.json");
+    let version: Option<Value> = read_as_value(&version_path).await.ok();
+    let version: Value = match version {
+        Some(version) => version,
+        None => {
+            let url: String = get_version(&id).await?.url;
+            file::download_as_json(
+                &url,
+                "",
+                &ChecksumType::SHA1,
+                &version_path,
+                false,
+                true,
+                None,
+            )
+            .await?
+        }
+    };
+    let game_id: &str = parse_version_id(&id).0;
+
+    let mut targets: Vec<RepairTarget> = vec![];
+
+    // client.jar
+    targets.push(RepairTarget::new(
+        format!("versions/{game_id}.jar"),
+        version["downloads"]["client"]["url"]
+            .as_str()
+            .unwrap_or_default(),
+        version["downloads"]["client"]["sha1"]
+            .as_str()
+            .unwrap_or_default(),
+    ));
+
+    // libraries and natives
+    let mut natives: Vec<NativesTarget> = vec![];
+    add_library_targets(&version, game_id, &mut targets, &mut natives);
+    if let Some(modloader_manifest) = &modloader_manifest {
+        add_library_targets(modloader_manifest, game_id, &mut targets, &mut natives);
+    }
+    for addon in instance_info.addons.iter() {
+        if let Some(addon_manifest) =
+            loader::resolve_manifest(addon, &instance_info.version, app, name).await?
+        {
+            add_library_targets(&addon_manifest, game_id, &mut targets, &mut natives);
+        }
+    }
+
+    // logging
+    let log_file: &Value = &version["logging"]["client"]["file"];
+    if let Some(log_id) = log_file["id"].as_str() {
+        targets.push(RepairTarget::new(
+            format!("assets/log_configs/{log_id}"),
+            log_file["url"].as_str().unwrap_or_default(),
+            log_file["sha1"].as_str().unwrap_or_default(),
+        ));
+    }
+
+    // assets, the index has to be right before its objects can be checked
+    let asset_index_id: &str = version["assetIndex"]["id"].as_str().unwrap_or_default();
+    let mut asset_objects: Vec<(String, String)> = vec![];
+    // objects whose hash can't name a file, they can't be checked nor downloaded
+    let mut malformed: Vec<String> = vec![];
+    if !asset_index_id.is_empty() {
+        let index_target: RepairTarget = RepairTarget::new(
+            format!("assets/indexes/{asset_index_id}.json"),
+            version["assetIndex"]["url"].as_str().unwrap_or_default(),
+            version["assetIndex"]["sha1"].as_str().unwrap_or_default(),
+        );
+        let assets: Value = file::download_as_json(
+            &index_target.url,
+            &index_target.sha1,
+            &ChecksumType::SHA1,
+            &index_target.path,
+            false,
+            !index_target.is_valid(),
+            None,
+        )
+        .await?;
+
+        if let Some(objects) = assets["objects"].as_object() {
+            for (object_name, object) in objects {
+                let hash: &str = object["hash"].as_str().unwrap_or_default();
+                let prefix: &str = match hash.get(0..2) {
+                    Some(prefix) => prefix,
+                    None => {
+                        write_line(&format!(
+                            "The asset index {asset_index_id} lists an invalid hash for {object_name}"
+                        ));
+                        malformed.push(format!("assets/{object_name}"));
+                        continue;
+                    }
+                };
+                let url: String = match object["custom_url"].as_str() {
+                    Some(custom_url) => custom_url.to_string(),
+                    None => format!("{MINECRAFT_RESOURCES_BASE}/{prefix}/{hash}"),
+                };
+                let path: String = format!("assets/objects/{prefix}/{hash}");
+                targets.push(RepairTarget::new(path.clone(), &url, hash));
+                asset_objects.push((object_name.to_string(), path));
+            }
+        }
+    }
+
+    let checked: u64 = (targets.len() + natives.len() + malformed.len()) as u64;
+
+    // hashing thousands of objects is cpu bound, keep it off the async runtime
+    let broken: Vec<RepairTarget> = tauri::async_runtime::spawn_blocking(move || {
+        targets
+            .into_par_iter()
+            .filter(|target| !target.is_valid())
+            .collect::<Vec<RepairTarget>>()
+    })
+    .await?;
+
+    emit_progress(app, name, &format!("Repairing {} files", broken.len()));
+
+    let results: Vec<(String, bool)> = stream::iter(broken.into_iter().map(|target| {
+        let handle_copy: AppHandle = app.clone();
+        let instance_name_copy: String = name.to_string();
+        let path: String = target.path.clone();
+        async move {
+            let repaired: bool = async_runtime::spawn(async move {
+                if target.url.is_empty() {
+                    return false;
+                }
+                match file::download_as_vec(
+                    &target.url,
+                    &target.sha1,
+                    &ChecksumType::SHA1,
+                    &target.path,
+                    false,
+                    true,
+                    Some((&handle_copy, &instance_name_copy)),
+                )
+                .await
+                {
+                    Ok(_) => true,
+                    Err(err) => {
+                        write_line(&format!("Failed to repair {}: {err}", target.path));
+                        false
+                    }
+                }
+            })
+            .await
+            .unwrap_or(false);
+            (path, repaired)
+        }
+    }))
+    .buffer_unordered(50)
+    .collect::<Vec<_>>()
+    .await;
+
+    let mut report: RepairReport = RepairReport {
+        checked,
+        repaired: vec![],
+        failed: malformed,
+    };
+    for (path, repaired) in results {
+        if repaired {
+            report.repaired.push(path);
+        } else {
+            report.failed.push(path);
+        }
+    }
+    for target in natives {
+        match repair_natives(&target).await.map_err(|err| err.to_string()) {
+            Ok(repaired) => report.repaired.extend(repaired),
+            Err(err) => {
+                write_line(&format!("Failed to repair {}: {err}", target.url));
+                report.failed.push(target.folder);
+            }
+        }
+    }
+
+    // old versions read their assets from a virtual copy
+    if asset_index_id == "legacy" || asset_index_id == "1.7.10" {
+        for (object_name, object_path) in asset_objects {
+            let virtual_path: String = format!("assets/virtual/legacy/{object_name}");
+            if get_data_root().join(&virtual_path).is_file() {
+                continue;
+            }
+            match file::read_as_vec(&object_path).await {
+                Ok(bytes) => {
+                    file::write_vec(&bytes, &virtual_path)?;
+                    report.repaired.push(virtual_path);
+                }
+                Err(_) => report.failed.push(virtual_path),
+            }
+        }
+    }
+
+    if let Some(modloader_manifest) = &modloader_manifest {
+        repair_installer_outputs(
+            &instance_info.modloader,
+            &id,
+            modloader_manifest,
+            app,
+            name,
+            &mut report,
+        )
+        .await?;
+    }
+
+    emit_status(app, name, "", "Success");
+
+    Ok(report)
+}
+
+fn add_library_targets(
+    manifest: &Value,
+    game_id: &str,
+    targets: &mut Vec<RepairTarget>,
+    natives_targets: &mut Vec<NativesTarget>,
+) {
+    let libraries: &Vec<Value> = match manifest["libraries"].as_array() {
+        Some(libraries) => libraries,
+        None => return,
+    };
+
+    for library in libraries {
+        if !must_download(library) {
+            continue;
+        }
+        if let Some(artifact) = library["downloads"].get("artifact") {
+            let path: &str = artifact["path"].as_str().unwrap_or_default();
+            targets.push(RepairTarget::new(
+                format!("libraries/{path}"),
+                &artifact_url(artifact),
+                artifact["sha1"].as_str().unwrap_or_default(),
+            ));
+        }
+        if let Some(natives_classifier) = natives_classifier(library) {
+            if let Some(natives) = library["downloads"]["classifiers"].get(natives_classifier) {
+                let target: NativesTarget = NativesTarget {
+                    folder: format!("natives/{game_id}"),
+                    url: natives["url"].as_str().unwrap_or_default().to_string(),
+                    sha1: natives["sha1"].as_str().unwrap_or_default().to_string(),
+                };
+                // the game and its loader often list the same natives
+                if !target.url.is_empty() && !natives_targets.contains(&target) {
+                    natives_targets.push(target);
+                }
+            }
+        }
+        if let (Some(url), Some(name)) = (library["url"].as_str(), library["name"].as_str()) {
+            let path: String = library_name_to_raw_path(name);
+            targets.push(RepairTarget::new(
+                format!("libraries/{path}"),
+                &format!("{url}{path}"),
+                library["sha1"].as_str().unwrap_or_default(),
+            ));
+        }
+    }
+}
+
+/// The files of a natives jar that are missing from `folder` or differ in size, as they would be
+/// extracted.
+fn missing_natives(jar: &[u8], folder: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut archive: ZipArchive<Cursor<&[u8]>> = ZipArchive::new(Cursor::new(jar))?;
+    let mut missing: Vec<String> = vec![];
+    for index in 0..archive.len() {
+        let entry: ZipFile = archive.by_index(index)?;
+        if entry.is_dir() || entry.name().starts_with("META-INF/") {
+            continue;
+        }
+        let path: PathBuf = safe_join(folder, entry.name())?;
+        let size: Option<u64> = fs::metadata(path).ok().map(|metadata| metadata.len());
+        if size != Some(entry.size()) {
+            missing.push(entry.name().to_string());
+        }
+    }
+    Ok(missing)
+}
+
+// natives are only kept extracted, so the jar is fetched again to see what the folder should hold
+async fn repair_natives(target: &NativesTarget) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let jar: Vec<u8> = file::download_as_vec(
+        &target.url,
+        &target.sha1,
+        &ChecksumType::SHA1,
+        "",
+        false,
+        true,
+        None,
+    )
+    .await?;
+    let folder: PathBuf = get_data_root().join(&target.folder);
+    let missing: Vec<String> = missing_natives(&jar, &folder)?;
+    if missing.is_empty() {
+        return Ok(vec![]);
+    }
+
+    fs::create_dir_all(&folder)?;
+    extract_zip(folder, &mut Cursor::new(jar)).await?;
+    Ok(missing
+        .iter()
+        .map(|entry| format!("{}/{entry}", target.folder))
+        .collect())
+}
+
+// installer based loaders produce their jars locally, so they are rebuilt by running the
+// installer steps again
+async fn repair_installer_outputs(
+    modloader: &InstanceModLoader,
+    id: &str,
+    manifest: &Value,
+    app: &AppHandle,
+    name: &str,
+    report: &mut RepairReport,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (client_jar, install_profile_path): (String, String) = match modloader {
+        InstanceModLoader::Forge(forge_version) => (
+            format!(
+                "libraries/net/minecraftforge/forge/{forge_version}/forge-{forge_version}-client.jar"
+            ),
+            format!("launcher/meta/net.minecraftforge/{forge_version}-install.json"),
+        ),
+        InstanceModLoader::NeoForge(neoforge_version) => (
+            neoforge::client_jar_path(neoforge_version),
+            format!("launcher/meta/net.neoforged/{neoforge_version}-install.json"),
+        ),
+        _ => return Ok(()),
+    };
+
+    let mut broken_outputs: Vec<String> = vec![];
+
+    if let InstanceModLoader::Forge(forge_version) = modloader {
+        if manifest["patches"].is_array() {
+            let patched_jar: String = format!("versions/forge-{forge_version}.jar");
+            if !get_data_root().join(&patched_jar).is_file() {
+                broken_outputs.push(patched_jar);
+            }
+        }
+    }
+
+    if let Ok(install_profile) = read_as_value::<Value>(&install_profile_path).await {
+        if install_profile["processors"].is_array() && !get_data_root().join(&client_jar).is_file()
+        {
+            broken_outputs.push(client_jar.clone());
+        }
+        for processor in install_profile["processors"].as_array().unwrap_or(&vec![]) {
+            for (output, sha1) in forge::processor_outputs(processor, &install_profile["data"]) {
+                let target: RepairTarget = RepairTarget::new(output, "", &sha1);
+                if !target.is_valid() {
+                    broken_outputs.push(target.path);
+                }
+            }
+        }
+    }
+
+    if broken_outputs.is_empty() {
+        return Ok(());
+    }
+
+    // the processors only run when the client jar is missing
+    let _ = fs::remove_file(get_data_root().join(&client_jar));
+
+    match loader::install(modloader, id, app, name).await {
+        Ok(_) => report.repaired.extend(broken_outputs),
+        Err(err) => {
+            write_line(&format!("Failed to rebuild {modloader}: {err}"));
+            report.failed.extend(broken_outputs);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::{write::FileOptions, ZipWriter};
+
+    #[test]
+    fn finds_each_missing_native() {
+        let mut writer: ZipWriter<Cursor<Vec<u8>>> = ZipWriter::new(Cursor::new(vec![]));
+        for (name, contents) in [
+            ("liblwjgl.so", &b"lwjgl"[..]),
+            ("libopenal.so", b"openal"),
+            ("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0"),
+        ] {
+            writer.start_file(name, FileOptions::default()).unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        let jar: Vec<u8> = writer.finish().unwrap().into_inner();
+
+        let folder: PathBuf =
+            std::env::temp_dir().join(format!("atlas-natives-{}", rand::random::<u32>()));
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("liblwjgl.so"), b"lwjgl").unwrap();
+        fs::write(folder.join("libopenal.so"), b"trunc").unwrap();
+        let missing: Vec<String> = missing_natives(&jar, &folder).unwrap();
+        fs::write(folder.join("libopenal.so"), b"openal").unwrap();
+        let complete: Vec<String> = missing_natives(&jar, &folder).unwrap();
+        fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(missing, vec!["libopenal.so"]);
+        assert!(complete.is_empty());
+    }
+}
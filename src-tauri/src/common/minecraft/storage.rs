@@ -0,0 +1,393 @@
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{
+    common::{
+        modloader::neoforge,
+        utils::{
+            directory::{get_data_root, list_directory, walk_files},
+            file::{library_name_to_raw_path, read_as_value},
+            log::write_line,
+        },
+    },
+    data::models::{GarbageEntry, GarbageReport, InstanceInfo, InstanceModLoader},
+};
+
+use super::versions::parse_version_id;
+
+// held for reading by installs, whose files aren't referenced by an instance config until they
+// are done, and for writing by the garbage collection
+static INSTALLS: RwLock<()> = RwLock::const_new(());
+
+/// Keeps the garbage collection from running until the returned guard is dropped. Taken by
+/// everything that downloads into the shared folders, until the instance config is written.
+pub async fn lock_for_install() -> RwLockReadGuard<'static, ()> {
+    INSTALLS.read().await
+}
+
+/// Every shared file and directory referenced by at least one instance.
+#[derive(Default)]
+pub struct ReferenceIndex {
+    files: HashSet<PathBuf>,
+    directories: HashSet<PathBuf>,
+}
+
+impl ReferenceIndex {
+    fn add_file(&mut self, path: &str) {
+        self.files.insert(get_data_root().join(path));
+    }
+
+    fn add_directory(&mut self, path: &str) {
+        self.directories.insert(get_data_root().join(path));
+    }
+
+    fn add_library(&mut self, library: &Value) {
+        if let Some(path) = library["downloads"]["artifact"]["path"].as_str() {
+            self.add_file(&format!("libraries/{path}"));
+        }
+        if let Some(name) = library["name"].as_str() {
+            if name.split(':').count() >= 3 {
+                self.add_file(&format!("libraries/{}", library_name_to_raw_path(name)));
+            }
+        }
+    }
+
+    fn add_libraries(&mut self, manifest: &Value) {
+        if let Some(libraries) = manifest["libraries"].as_array() {
+            for library in libraries {
+                self.add_library(library);
+            }
+        }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains(path) || self.directories.contains(path)
+    }
+}
+
+pub async fn build_reference_index() -> Result<ReferenceIndex, Box<dyn std::error::Error>> {
+    let mut index: ReferenceIndex = ReferenceIndex::default();
+
+    for instance_path in list_directory(&get_data_root().join("instances")) {
+        if !instance_path.is_dir() {
+            continue;
+        }
+        let name: String = instance_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        // an instance with a broken config still uses its files, so nothing is safe to remove
+        let instance: InstanceInfo =
+            match read_as_value(&format!("instances/{name}/atlas_instance.json")).await {
+                Ok(instance) => instance,
+                Err(err) => {
+                    return Err(format!("Could not read the config of {name}: {err}").into())
+                }
+            };
+        if let Err(err) = index_instance(&mut index, &instance).await {
+            // without the manifests we can't tell what the instance needs, so nothing is safe to remove
+            return Err(format!(
+                "Could not resolve the files used by {}: {err}",
+                instance.name
+            )
+            .into());
+        }
+    }
+
+    Ok(index)
+}
+
+async fn index_instance(
+    index: &mut ReferenceIndex,
+    instance: &InstanceInfo,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut id: String = instance.version.clone();
+
+    if let Some(inherits_from) =
+        index_modloader(index, &instance.modloader, &instance.version).await?
+    {
+        id = inherits_from;
+    }
+    for addon in instance.addons.iter() {
+        index_modloader(index, addon, &instance.version).await?;
+    }
+
+    let game_id: &str = parse_version_id(&id).0;
+
+    let version: Value = read_as_value(&format!("launcher/meta/net.minecraft/{id}.json")).await?;
+    index.add_libraries(&version);
+    index.add_file(&format!("versions/{game_id}.jar"));
+    index.add_directory(&format!("natives/{game_id}"));
+    index.add_directory(&format!("natives/{}", instance.version));
+
+    if let Some(log_id) = version["logging"]["client"]["file"]["id"].as_str() {
+        index.add_file(&format!("assets/log_configs/{log_id}"));
+    }
+
+    if let Some(asset_index) = version["assetIndex"]["id"].as_str() {
+        index.add_file(&format!("assets/indexes/{asset_index}.json"));
+        if asset_index == "legacy" || asset_index == "1.7.10" {
+            index.add_directory("assets/virtual/legacy");
+        }
+
+        let assets: Value = read_as_value(&format!("assets/indexes/{asset_index}.json")).await?;
+        if let Some(objects) = assets["objects"].as_object() {
+            for object in objects.values() {
+                if let Some(hash) = object["hash"].as_str() {
+                    let prefix: &str = hash.get(0..2).ok_or_else(|| {
+                        format!("The asset index {asset_index} lists an invalid hash {hash}")
+                    })?;
+                    index.add_file(&format!("assets/objects/{prefix}/{hash}"));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// returns the version the loader inherits from when it differs from the instance version
+async fn index_modloader(
+    index: &mut ReferenceIndex,
+    modloader: &InstanceModLoader,
+    game_version: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    match modloader {
+        InstanceModLoader::Vanilla => {}
+        InstanceModLoader::Forge(forge) => {
+            let manifest: Value =
+                read_as_value(&format!("launcher/meta/net.minecraftforge/{forge}.json")).await?;
+            index.add_libraries(&manifest);
+            index.add_file(&format!("versions/forge-{forge}.jar"));
+            index.add_file(&format!(
+                "libraries/net/minecraftforge/forge/{forge}/forge-{forge}-client.jar"
+            ));
+            index_installer(
+                index,
+                &format!("launcher/meta/net.minecraftforge/{forge}"),
+                &format!("launcher/cache/{forge}"),
+            )
+            .await;
+
+            if let Some(inherits_from) = manifest["inheritsFrom"].as_str() {
+                return Ok(Some(inherits_from.to_string()));
+            }
+        }
+        InstanceModLoader::NeoForge(neoforge) => {
+            let manifest: Value =
+                read_as_value(&format!("launcher/meta/net.neoforged/{neoforge}.json")).await?;
+            index.add_libraries(&manifest);
+            index.add_file(&neoforge::client_jar_path(neoforge));
+            index_installer(
+                index,
+                &format!("launcher/meta/net.neoforged/{neoforge}"),
+                &format!("launcher/cache/neoforge-{neoforge}"),
+            )
+            .await;
+
+            if let Some(inherits_from) = manifest["inheritsFrom"].as_str() {
+                return Ok(Some(inherits_from.to_string()));
+            }
+        }
+        InstanceModLoader::OptiFine(optifine) => {
+            let manifest: Value =
+                read_as_value(&format!("launcher/meta/optifine/{optifine}.json")).await?;
+            index.add_libraries(&manifest);
+        }
+        InstanceModLoader::LiteLoader(liteloader) => {
+            let manifest: Value = read_as_value(&format!(
+                "launcher/meta/com.mumfrey.liteloader/{liteloader}.json"
+            ))
+            .await?;
+            index.add_libraries(&manifest);
+        }
+        InstanceModLoader::Fabric(fabric) => {
+            let manifest: Value = read_as_value(&format!(
+                "launcher/meta/net.fabricmc/{fabric}-{game_version}.json"
+            ))
+            .await?;
+            index.add_libraries(&manifest);
+        }
+        InstanceModLoader::Quilt(quilt) => {
+            let manifest: Value = read_as_value(&format!(
+                "launcher/meta/org.quiltmc/{quilt}-{game_version}.json"
+            ))
+            .await?;
+            index.add_libraries(&manifest);
+        }
+    }
+
+    Ok(None)
+}
+
+// files the installer left in the cache and everything its install profile downloads or generates
+async fn index_installer(index: &mut ReferenceIndex, meta_path: &str, cache_path: &str) {
+    index.add_file(&format!("{cache_path}-client.lzma"));
+    index.add_file(&format!("{cache_path}-installer.jar"));
+
+    if let Ok(install_profile) = read_as_value::<Value>(&format!("{meta_path}-install.json")).await
+    {
+        index.add_libraries(&install_profile);
+        if let Some(data) = install_profile["data"].as_object() {
+            for value in data.values() {
+                if let Some(library) = value["client"].as_str() {
+                    if library.starts_with('[') && library.ends_with(']') {
+                        index.add_file(&format!(
+                            "libraries/{}",
+                            library_name_to_raw_path(&library[1..library.len() - 1])
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Finds (and unless `dry_run` is set, removes) shared files no instance references anymore.
+/// Refuses to run while an instance is being installed.
+pub async fn collect_garbage(dry_run: bool) -> Result<GarbageReport, Box<dyn std::error::Error>> {
+    let _collecting: RwLockWriteGuard<'static, ()> = INSTALLS
+        .try_write()
+        .map_err(|_| "An install is in progress, try again once it is done")?;
+    let index: ReferenceIndex = build_reference_index().await?;
+    let root: PathBuf = get_data_root();
+
+    let mut entries: Vec<GarbageEntry> = vec![];
+
+    // shared folders whose files are referenced one by one
+    for (folder, r#type) in [
+        ("libraries", "library"),
+        ("assets/objects", "asset"),
+        ("assets/indexes", "asset_index"),
+        ("versions", "version"),
+    ] {
+        for path in walk_files(&root.join(folder)) {
+            if !index.contains(&path) {
+                entries.push(garbage_entry(path, r#type));
+            }
+        }
+    }
+
+    // folders that are referenced as a whole
+    for (folder, r#type) in [("natives", "natives"), ("assets/virtual", "asset")] {
+        for path in list_directory(&root.join(folder)) {
+            if path.is_dir() && !index.contains(&path) {
+                entries.push(garbage_entry(path, r#type));
+            }
+        }
+    }
+
+    for path in list_directory(&root.join("launcher/cache")) {
+        let extension: &OsStr = path.extension().unwrap_or_default();
+        if (extension == "lzma" || extension == "jar") && !index.contains(&path) {
+            entries.push(garbage_entry(path, "cache"));
+        }
+    }
+
+    let reclaimable_bytes: u64 = entries.iter().map(|entry| entry.size).sum();
+
+    if !dry_run {
+        for entry in entries.iter() {
+            let path: &Path = Path::new(&entry.path);
+            let result: std::io::Result<()> = if path.is_dir() {
+                fs::remove_dir_all(path)
+            } else {
+                fs::remove_file(path)
+            };
+            match result {
+                Ok(_) => remove_empty_parents(path, &root),
+                Err(err) => write_line(&format!("Failed to remove {}: {err}", entry.path)),
+            }
+        }
+    }
+
+    Ok(GarbageReport {
+        dry_run,
+        reclaimable_bytes,
+        entries,
+    })
+}
+
+fn garbage_entry(path: PathBuf, r#type: &str) -> GarbageEntry {
+    GarbageEntry {
+        size: size_of(&path),
+        path: path.to_str().unwrap_or_default().to_string(),
+        r#type: r#type.to_string(),
+    }
+}
+
+fn size_of(path: &Path) -> u64 {
+    if path.is_dir() {
+        return walk_files(path).iter().map(|file| size_of(file)).sum();
+    }
+    fs::metadata(path)
+        .map(|metadata| metadata.len())
+        .unwrap_or_default()
+}
+
+// libraries are nested deeply, don't leave empty folder trees behind
+fn remove_empty_parents(path: &Path, root: &Path) {
+    let mut current: Option<&Path> = path.parent();
+    while let Some(directory) = current {
+        if directory == root || fs::remove_dir(directory).is_err() {
+            return;
+        }
+        current = directory.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn libraries_are_referenced_by_artifact_and_name() {
+        let mut index: ReferenceIndex = ReferenceIndex::default();
+        index.add_libraries(&json!({
+            "libraries": [
+                {
+                    "name": "org.ow2.asm:asm:9.5",
+                    "downloads": { "artifact": { "path": "org/ow2/asm/asm/9.5/asm-9.5.jar" } }
+                },
+                { "name": "net.fabricmc:intermediary:1.20.1" },
+                { "name": "invalid" }
+            ]
+        }));
+
+        let root: PathBuf = get_data_root();
+        assert!(index.contains(&root.join("libraries/org/ow2/asm/asm/9.5/asm-9.5.jar")));
+        assert!(index.contains(
+            &root.join("libraries/net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar")
+        ));
+        assert!(!index.contains(&root.join("libraries/invalid")));
+    }
+
+    #[tokio::test]
+    async fn a_broken_instance_config_blocks_the_index() {
+        let name: String = format!("atlas-broken-{}", rand::random::<u32>());
+        let instance_path: PathBuf = get_data_root().join("instances").join(&name);
+        fs::create_dir_all(&instance_path).unwrap();
+        fs::write(instance_path.join("atlas_instance.json"), "{").unwrap();
+
+        let result: Result<ReferenceIndex, Box<dyn std::error::Error>> =
+            build_reference_index().await;
+        fs::remove_dir_all(&instance_path).unwrap();
+        assert!(result.err().unwrap().to_string().contains(&name));
+    }
+
+    #[tokio::test]
+    async fn garbage_collection_waits_for_installs() {
+        let install: RwLockReadGuard<'static, ()> = lock_for_install().await;
+        assert!(collect_garbage(true).await.is_err());
+        drop(install);
+    }
+}
@@ -1,18 +1,37 @@
-use std::error::Error;
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs,
+    path::PathBuf,
+};
 
-use crate::common::modloader::forge;
-use crate::common::utils::file::{self, read_as_value};
+use crate::common::java::get_java_path::runtime_version;
+use crate::common::modloader::{
+    fabric_meta::{MetaClient, FABRIC_META, QUILT_META},
+    forge, liteloader, neoforge, optifine,
+};
+use crate::common::utils::cache::fetch_metadata_json;
+use crate::common::utils::directory::get_data_root;
+use crate::common::utils::file;
+use crate::common::utils::log::write_line;
+use crate::common::utils::network::ensure_online;
 use crate::data::constants::{
-    BETTER_JSONS_VERSION_MANIFEST, EXTRA_VERSION_MANIFEST, FABRIC_VERSION_MANIFEST,
-    MINECRAFT_VERSION_MANIFEST, NET_FABRICMC_VERSION_MANIFEST, NET_MINECRAFTFORGE_VERSION_MANIFEST,
-    NET_MINECRAFT_VERSION_MANIFEST, ORG_QUILTMC_VERSION_MANIFEST, QUILT_VERSION_MANIFEST,
+    BETTER_JSONS_VERSION_MANIFEST, EXTRA_VERSION_MANIFEST, MINECRAFT_VERSION_MANIFEST,
+    NET_MINECRAFTFORGE_VERSION_MANIFEST, NET_MINECRAFT_BETTER_JSONS_VERSION_MANIFEST,
+    NET_MINECRAFT_EXTRA_VERSION_MANIFEST, NET_MINECRAFT_MOJANG_VERSION_MANIFEST,
+    NET_MINECRAFT_VERSION_MANIFEST,
+};
+use crate::data::models::{
+    FabricGameVersion, FabricLoaderVersion, MinecraftVersionData, ModLoaderType,
+    VersionManifestEntry, VersionQuery, VersionSource, VersionType,
 };
-use crate::data::models::MinecraftVersionData;
 use futures::future::join_all;
 use serde_json::Value;
 use tauri::async_runtime;
 
 pub async fn download_version_manifests() -> Result<(), Box<dyn std::error::Error>> {
+    ensure_online()?;
+
     let mut download_tasks: Vec<
         async_runtime::JoinHandle<Result<(), Box<dyn Error + Send + Sync>>>,
     > = vec![];
@@ -21,14 +40,10 @@ pub async fn download_version_manifests() -> Result<(), Box<dyn std::error::Erro
     let download_task: async_runtime::JoinHandle<
         Result<(), Box<dyn std::error::Error + Send + Sync>>,
     > = tauri::async_runtime::spawn(async move {
-        let version_manifest: Value = match file::download_as_json(
+        let version_manifest: Value = match fetch_metadata_json(
             MINECRAFT_VERSION_MANIFEST,
-            "",
-            &file::ChecksumType::SHA1,
-            "",
+            NET_MINECRAFT_MOJANG_VERSION_MANIFEST,
             false,
-            true,
-            None,
         )
         .await
         {
@@ -42,14 +57,10 @@ pub async fn download_version_manifests() -> Result<(), Box<dyn std::error::Erro
             }
         };
 
-        let better_jsons: Value = match file::download_as_json(
+        let better_jsons: Value = match fetch_metadata_json(
             BETTER_JSONS_VERSION_MANIFEST,
-            "",
-            &file::ChecksumType::SHA1,
-            "",
+            NET_MINECRAFT_BETTER_JSONS_VERSION_MANIFEST,
             false,
-            true,
-            None,
         )
         .await
         {
@@ -63,14 +74,10 @@ pub async fn download_version_manifests() -> Result<(), Box<dyn std::error::Erro
             }
         };
 
-        let extra_version_manifest: Value = match file::download_as_json(
+        let extra_version_manifest: Value = match fetch_metadata_json(
             EXTRA_VERSION_MANIFEST,
-            "",
-            &file::ChecksumType::SHA1,
-            "",
+            NET_MINECRAFT_EXTRA_VERSION_MANIFEST,
             false,
-            true,
-            None,
         )
         .await
         {
@@ -83,46 +90,46 @@ pub async fn download_version_manifests() -> Result<(), Box<dyn std::error::Erro
                     as Box<dyn std::error::Error + Send + Sync>);
             }
         };
-        let mut versions: Vec<Value> = version_manifest["versions"].as_array().unwrap().to_owned();
-
-        // cherrypick some versions to use with forge, as they are not compatible with the custom launch wrapper
-        let mut duplicate_versions: Vec<Value> = vec![];
-        let versions_to_keep: Vec<&str> = vec![
-            "1.3.2", "1.4", "1.4.1", "1.4.2", "1.4.3", "1.4.4", "1.4.5", "1.4.6", "1.4.7", "1.5",
-            "1.5.1", "1.5.2",
-        ];
-
-        for version in versions.iter() {
-            if let Some(id) = version["id"].as_str() {
-                if versions_to_keep.contains(&id) {
-                    let sha1: &str = version["sha1"].as_str().unwrap();
-                    let url: &str = version["url"].as_str().unwrap();
-                    duplicate_versions.push(
-                        serde_json::from_str(&format!(
-                            "{{\"id\": \"_{id}\",\"sha1\": \"{sha1}\",\"url\": \"{url}\"}}"
-                        ))
-                        .unwrap(),
-                    )
-                }
+        let versions: Vec<VersionManifestEntry> =
+            merge_version_manifests(&version_manifest, &better_jsons, &extra_version_manifest);
+
+        match file::write_value(&versions, NET_MINECRAFT_VERSION_MANIFEST) {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("{err}"),
+                ))
+                    as Box<dyn std::error::Error + Send + Sync>);
             }
         }
+    });
 
-        // the last 349 entries are already in betterjsons
-        versions.truncate(versions.len() - 349);
-        let better_jsons_versions: Vec<Value> =
-            better_jsons["versions"].as_array().unwrap().to_owned();
-        let extra_versions: Vec<Value> = extra_version_manifest["versions"]
-            .as_array()
-            .unwrap()
-            .to_owned();
+    download_tasks.push(download_task);
+
+    // forge
+    let download_task: async_runtime::JoinHandle<
+        Result<(), Box<dyn std::error::Error + Send + Sync>>,
+    > = tauri::async_runtime::spawn(async move {
+        match forge::download_versions().await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("{err}"),
+                ))
+                    as Box<dyn std::error::Error + Send + Sync>);
+            }
+        }
+    });
 
-        versions.extend(better_jsons_versions);
-        versions.extend(extra_versions);
-        versions.sort_by_key(|value| value["releaseTime"].as_str().unwrap_or("").to_lowercase());
-        versions.reverse();
-        versions.extend(duplicate_versions);
+    download_tasks.push(download_task);
 
-        match file::write_value(&versions, NET_MINECRAFT_VERSION_MANIFEST) {
+    // neoforge
+    let download_task: async_runtime::JoinHandle<
+        Result<(), Box<dyn std::error::Error + Send + Sync>>,
+    > = tauri::async_runtime::spawn(async move {
+        match neoforge::download_versions().await {
             Ok(_) => Ok(()),
             Err(err) => {
                 return Err(Box::new(std::io::Error::new(
@@ -136,11 +143,11 @@ pub async fn download_version_manifests() -> Result<(), Box<dyn std::error::Erro
 
     download_tasks.push(download_task);
 
-    // forge
+    // liteloader
     let download_task: async_runtime::JoinHandle<
         Result<(), Box<dyn std::error::Error + Send + Sync>>,
     > = tauri::async_runtime::spawn(async move {
-        match forge::download_versions().await {
+        match liteloader::download_versions().await {
             Ok(_) => Ok(()),
             Err(err) => {
                 return Err(Box::new(std::io::Error::new(
@@ -158,17 +165,7 @@ pub async fn download_version_manifests() -> Result<(), Box<dyn std::error::Erro
     let download_task: async_runtime::JoinHandle<
         Result<(), Box<dyn std::error::Error + Send + Sync>>,
     > = tauri::async_runtime::spawn(async move {
-        match file::download_as_json(
-            FABRIC_VERSION_MANIFEST,
-            "",
-            &file::ChecksumType::SHA1,
-            NET_FABRICMC_VERSION_MANIFEST,
-            false,
-            true,
-            None,
-        )
-        .await
-        {
+        match FABRIC_META.download_versions().await {
             Ok(_) => Ok(()),
             Err(err) => {
                 return Err(Box::new(std::io::Error::new(
@@ -186,17 +183,7 @@ pub async fn download_version_manifests() -> Result<(), Box<dyn std::error::Erro
     let download_task: async_runtime::JoinHandle<
         Result<(), Box<dyn std::error::Error + Send + Sync>>,
     > = tauri::async_runtime::spawn(async move {
-        match file::download_as_json(
-            QUILT_VERSION_MANIFEST,
-            "",
-            &file::ChecksumType::SHA1,
-            ORG_QUILTMC_VERSION_MANIFEST,
-            false,
-            true,
-            None,
-        )
-        .await
-        {
+        match QUILT_META.download_versions().await {
             Ok(_) => Ok(()),
             Err(err) => {
                 return Err(Box::new(std::io::Error::new(
@@ -214,84 +201,453 @@ pub async fn download_version_manifests() -> Result<(), Box<dyn std::error::Erro
     Ok(())
 }
 
-pub async fn get_versions() -> Result<Vec<MinecraftVersionData>, Box<dyn std::error::Error>> {
-    let data: serde_json::Value = file::read_as_value(NET_MINECRAFT_VERSION_MANIFEST)
-        .await
-        .unwrap();
+// these versions are not compatible with the custom launch wrapper, forge uses their original json
+static FORGE_COMPATIBLE_VERSIONS: [&str; 12] = [
+    "1.3.2", "1.4", "1.4.1", "1.4.2", "1.4.3", "1.4.4", "1.4.5", "1.4.6", "1.4.7", "1.5", "1.5.1",
+    "1.5.2",
+];
 
-    let versions: &Vec<serde_json::Value> = data.as_array().ok_or("Invalid manifest JSON")?;
+/// Merges the upstream manifests keyed by version id. When several sources list the same id the
+/// one with the highest precedence wins, see [`precedence`]. Forge compatible variants of the
+/// Mojang entries are appended after the sorted list.
+pub fn merge_version_manifests(
+    mojang: &Value,
+    better_jsons: &Value,
+    extra: &Value,
+) -> Vec<VersionManifestEntry> {
+    let mut merged: HashMap<String, VersionManifestEntry> = HashMap::new();
 
-    let minecraft_versions: Vec<MinecraftVersionData> = versions
+    for (manifest, source) in [
+        (mojang, VersionSource::Mojang),
+        (better_jsons, VersionSource::BetterJsons),
+        (extra, VersionSource::Extra),
+    ] {
+        for entry in manifest_entries(manifest, source) {
+            let replace: bool = match merged.get(&entry.id) {
+                Some(existing) => precedence(source) >= precedence(existing.source),
+                None => true,
+            };
+            if replace {
+                merged.insert(entry.id.clone(), entry);
+            }
+        }
+    }
+
+    let mut versions: Vec<VersionManifestEntry> = merged.into_values().collect();
+    sort_by_release(&mut versions);
+
+    let mut forge_compatible: Vec<VersionManifestEntry> =
+        manifest_entries(mojang, VersionSource::Mojang)
+            .into_iter()
+            .filter(|entry| FORGE_COMPATIBLE_VERSIONS.contains(&entry.id.as_str()))
+            .map(|mut entry| {
+                entry.forge_compatible = true;
+                entry
+            })
+            .collect();
+    sort_by_release(&mut forge_compatible);
+    versions.extend(forge_compatible);
+
+    versions
+}
+
+// BetterJSONs replaces the broken legacy Mojang jsons, the extra manifest only fills in
+// versions nobody else lists
+fn precedence(source: VersionSource) -> u8 {
+    match source {
+        VersionSource::Extra => 0,
+        VersionSource::Mojang => 1,
+        VersionSource::BetterJsons => 2,
+    }
+}
+
+fn manifest_entries(manifest: &Value, source: VersionSource) -> Vec<VersionManifestEntry> {
+    let versions: &Vec<Value> = match manifest["versions"].as_array() {
+        Some(versions) => versions,
+        None => {
+            write_line(&format!("{source:?} version manifest has no versions"));
+            return vec![];
+        }
+    };
+
+    versions
         .iter()
-        .map(|version| MinecraftVersionData {
-            id: version["id"].as_str().unwrap_or("").to_owned(),
-            url: version["url"].as_str().unwrap_or("").to_owned(),
-            r#type: version["type"].as_str().unwrap_or("").to_owned(),
+        .filter_map(|version| {
+            match serde_json::from_value::<VersionManifestEntry>(version.to_owned()) {
+                Ok(mut entry) if !entry.id.is_empty() && !entry.url.is_empty() => {
+                    entry.source = source;
+                    entry.forge_compatible = false;
+                    Some(entry)
+                }
+                Ok(_) => None,
+                Err(err) => {
+                    write_line(&format!("Skipping {source:?} version {version}: {err}"));
+                    None
+                }
+            }
         })
+        .collect()
+}
+
+fn sort_by_release(versions: &mut [VersionManifestEntry]) {
+    versions.sort_by(|a, b| {
+        b.release_time
+            .to_lowercase()
+            .cmp(&a.release_time.to_lowercase())
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// Forge manifests refer to the forge compatible variant of a version as `_<id>`.
+pub fn parse_version_id(id: &str) -> (&str, bool) {
+    match id.strip_prefix('_') {
+        Some(id) => (id, true),
+        None => (id, false),
+    }
+}
+
+async fn read_manifest() -> Result<Vec<VersionManifestEntry>, Box<dyn std::error::Error>> {
+    file::read_as_value(NET_MINECRAFT_VERSION_MANIFEST).await
+}
+
+pub async fn get_versions() -> Result<Vec<MinecraftVersionData>, Box<dyn std::error::Error>> {
+    let minecraft_versions: Vec<MinecraftVersionData> = read_manifest()
+        .await?
+        .into_iter()
+        .filter(|version| !version.forge_compatible)
+        .map(|version| version_data(version, None))
         .collect();
 
     Ok(minecraft_versions)
 }
 
 pub async fn get_version(id: &str) -> Result<MinecraftVersionData, Box<dyn std::error::Error>> {
-    let data: serde_json::Value = file::read_as_value(NET_MINECRAFT_VERSION_MANIFEST)
-        .await
-        .unwrap();
+    let (version_id, forge_compatible) = parse_version_id(id);
 
-    let versions: &Vec<serde_json::Value> = data.as_array().ok_or("Invalid manifest JSON")?;
+    let minecraft_version: Option<MinecraftVersionData> = read_manifest()
+        .await?
+        .into_iter()
+        .find(|version| version.id == version_id && version.forge_compatible == forge_compatible)
+        .map(|version| version_data(version, Some(id)));
 
-    let minecraft_version: Option<MinecraftVersionData> = versions.iter().find_map(|version| {
-        let version_id: &str = version["id"].as_str()?;
-        if version_id == id {
-            Some(MinecraftVersionData {
-                id: version_id.to_owned(),
-                url: version["url"].as_str().unwrap_or("").to_owned(),
-                r#type: version["type"].as_str().unwrap_or("").to_owned(),
-            })
-        } else {
-            None
+    minecraft_version.ok_or_else(|| format!("Version {id} not found in the manifest").into())
+}
+
+/// Lists the versions matching every filter of `query`.
+pub async fn query_versions(
+    query: &VersionQuery,
+) -> Result<Vec<MinecraftVersionData>, Box<dyn std::error::Error>> {
+    let mut versions: Vec<MinecraftVersionData> = get_versions().await?;
+
+    if !query.types.is_empty() {
+        versions.retain(|version| {
+            query
+                .types
+                .iter()
+                .any(|version_type| matches_type(version, *version_type))
+        });
+    }
+
+    if !query.modloaders.is_empty() {
+        let mut supported: HashSet<String> = HashSet::new();
+        for modloader in query.modloaders.iter() {
+            supported.extend(get_modloader_game_versions(*modloader).await);
         }
-    });
+        versions.retain(|version| supported.contains(&version.id));
+    }
+
+    let search: String = query.search.trim().to_lowercase();
+    if !search.is_empty() {
+        versions.retain(|version| version.id.to_lowercase().contains(&search));
+    }
+
+    Ok(versions)
+}
+
+fn version_data(version: VersionManifestEntry, id: Option<&str>) -> MinecraftVersionData {
+    let java_version: u8 = cached_java_version(&version.id)
+        .unwrap_or_else(|| runtime_version(required_java_version(&version.release_time)));
+    MinecraftVersionData {
+        id: id.map(|id| id.to_owned()).unwrap_or(version.id),
+        url: version.url,
+        r#type: version.r#type,
+        java_version,
+        release_time: version.release_time,
+        source: version.source,
+    }
+}
+
+/// The runtime a version json asks for, Java 8 when it predates `javaVersion`.
+pub fn java_version(version: &Value) -> u8 {
+    runtime_version(version["javaVersion"]["majorVersion"].as_u64().unwrap_or(8))
+}
+
+// the version json is only there once the version was installed
+fn cached_java_version(id: &str) -> Option<u8> {
+    let path: PathBuf = get_data_root().join(format!("launcher/meta/net.minecraft/{id}.json"));
+    let version: Value = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+    Some(java_version(&version))
+}
 
-    Ok(minecraft_version.unwrap())
+// until then it is estimated, the manifests don't list it but Mojang only raised it at these
+// releases: 21w19a (java 16), 1.18-pre2 (java 17), 24w14a (java 21) and 26.1-snapshot-1 (java 25)
+fn required_java_version(release_time: &str) -> u64 {
+    if release_time.is_empty() || release_time < "2021-05-12" {
+        8
+    } else if release_time < "2021-11-16" {
+        16
+    } else if release_time < "2024-04-03" {
+        17
+    } else if release_time < "2025-12-16" {
+        21
+    } else {
+        25
+    }
+}
+
+// Mojang lists the april fools versions as snapshots
+static APRIL_FOOLS_VERSIONS: [&str; 9] = [
+    "15w14a",
+    "1.RV-Pre1",
+    "3D Shareware v1.34",
+    "20w14infinite",
+    "22w13oneblockatatime",
+    "23w13a_or_b",
+    "24w14potato",
+    "25w14craftmine",
+    "2point0",
+];
+
+fn matches_type(version: &MinecraftVersionData, version_type: VersionType) -> bool {
+    let april_fools: bool = version.r#type == "april_fools"
+        || APRIL_FOOLS_VERSIONS
+            .iter()
+            .any(|id| version.id.starts_with(id));
+
+    match version_type {
+        VersionType::Release => version.r#type == "release",
+        VersionType::Snapshot => version.r#type == "snapshot",
+        VersionType::OldBeta => version.r#type == "old_beta",
+        VersionType::OldAlpha => version.r#type == "old_alpha",
+        VersionType::AprilFools => april_fools,
+    }
+}
+
+// game versions the loader has at least one build for, empty if its manifest isn't downloaded yet
+async fn get_modloader_game_versions(modloader: ModLoaderType) -> HashSet<String> {
+    match modloader {
+        ModLoaderType::Forge => get_forge_versions()
+            .await
+            .ok()
+            .and_then(|manifest| manifest.as_array().cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|entry| entry.as_object())
+            .flat_map(|entry| entry.keys().cloned().collect::<Vec<String>>())
+            .collect(),
+        ModLoaderType::NeoForge => neoforge::get_versions()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|version| neoforge::game_version(version))
+            .collect(),
+        ModLoaderType::LiteLoader => liteloader::get_game_versions()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .collect(),
+        ModLoaderType::OptiFine => optifine::get_versions()
+            .iter()
+            .filter_map(|version| optifine::game_version(version))
+            .map(|version| version.to_string())
+            .collect(),
+        ModLoaderType::Fabric | ModLoaderType::Quilt => {
+            get_fabric_mc_versions(modloader == ModLoaderType::Quilt)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|game| game.intermediary)
+                .map(|game| game.version)
+                .collect()
+        }
+    }
 }
 
 pub async fn get_forge_versions() -> Result<Value, Box<dyn std::error::Error>> {
-    let data: serde_json::Value = file::read_as_value(NET_MINECRAFTFORGE_VERSION_MANIFEST)
-        .await
-        .unwrap();
+    let data: serde_json::Value = file::read_as_value(NET_MINECRAFTFORGE_VERSION_MANIFEST).await?;
 
     Ok(data)
 }
 
 pub async fn get_fabric_mc_versions(
     is_quilt: bool,
-) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
-    let manifest: Value = read_as_value(if is_quilt {
-        ORG_QUILTMC_VERSION_MANIFEST
-    } else {
-        NET_FABRICMC_VERSION_MANIFEST
-    })
-    .await?;
-    if manifest["game"].is_array() {
-        return Ok(manifest["game"].as_array().unwrap().to_owned());
-    } else {
-        return Ok([].to_vec());
-    }
+) -> Result<Vec<FabricGameVersion>, Box<dyn std::error::Error>> {
+    MetaClient::for_quilt(is_quilt).game_versions().await
 }
 
 pub async fn get_fabric_loader_versions(
     is_quilt: bool,
-) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
-    let manifest: Value = read_as_value(if is_quilt {
-        ORG_QUILTMC_VERSION_MANIFEST
-    } else {
-        NET_FABRICMC_VERSION_MANIFEST
-    })
-    .await?;
-    if manifest["loader"].is_array() {
-        return Ok(manifest["loader"].as_array().unwrap().to_owned());
-    } else {
-        return Ok([].to_vec());
+) -> Result<Vec<FabricLoaderVersion>, Box<dyn std::error::Error>> {
+    MetaClient::for_quilt(is_quilt).loader_versions().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Value {
+        let path: String = format!(
+            "{}/tests/fixtures/version_manifests/{name}.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn merged() -> Vec<VersionManifestEntry> {
+        merge_version_manifests(
+            &fixture("mojang"),
+            &fixture("better_jsons"),
+            &fixture("extra"),
+        )
+    }
+
+    fn find(
+        versions: &[VersionManifestEntry],
+        id: &str,
+        forge_compatible: bool,
+    ) -> VersionManifestEntry {
+        versions
+            .iter()
+            .find(|version| version.id == id && version.forge_compatible == forge_compatible)
+            .unwrap()
+            .to_owned()
+    }
+
+    #[test]
+    fn keys_versions_by_id() {
+        let versions: Vec<VersionManifestEntry> = merged();
+        let ids: Vec<&str> = versions
+            .iter()
+            .filter(|version| !version.forge_compatible)
+            .map(|version| version.id.as_str())
+            .collect();
+
+        assert_eq!(
+            ids,
+            vec![
+                "1.20.1",
+                "1.5.2",
+                "2point0_red",
+                "1.4.7",
+                "c0.0.11a",
+                "rd-132211"
+            ]
+        );
+    }
+
+    #[test]
+    fn applies_source_precedence() {
+        let versions: Vec<VersionManifestEntry> = merged();
+
+        assert_eq!(
+            find(&versions, "1.5.2", false).source,
+            VersionSource::BetterJsons
+        );
+        assert_eq!(
+            find(&versions, "c0.0.11a", false).source,
+            VersionSource::BetterJsons
+        );
+        assert_eq!(
+            find(&versions, "1.20.1", false).source,
+            VersionSource::Mojang
+        );
+        assert_eq!(
+            find(&versions, "1.4.7", false).source,
+            VersionSource::Mojang
+        );
+        assert_eq!(
+            find(&versions, "2point0_red", false).source,
+            VersionSource::Extra
+        );
+    }
+
+    #[test]
+    fn appends_forge_compatible_variants() {
+        let versions: Vec<VersionManifestEntry> = merged();
+        let variants: Vec<&VersionManifestEntry> = versions
+            .iter()
+            .skip_while(|version| !version.forge_compatible)
+            .collect();
+
+        assert!(variants.iter().all(|version| version.forge_compatible));
+        assert_eq!(
+            variants
+                .iter()
+                .map(|version| version.id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["1.5.2", "1.4.7"]
+        );
+
+        let variant: VersionManifestEntry = find(&versions, "1.5.2", true);
+        assert_eq!(variant.source, VersionSource::Mojang);
+        assert_eq!(variant.sha1, "fd4f");
+        assert!(variant.url.starts_with("https://piston-meta.mojang.com"));
+    }
+
+    #[test]
+    fn tolerates_broken_manifests() {
+        let versions: Vec<VersionManifestEntry> =
+            merge_version_manifests(&fixture("mojang"), &Value::Null, &serde_json::json!({}));
+
+        assert_eq!(
+            versions
+                .iter()
+                .filter(|version| !version.forge_compatible)
+                .count(),
+            4
+        );
+        assert!(versions.iter().all(|version| !version.id.is_empty()));
+    }
+
+    #[test]
+    fn parses_legacy_version_ids() {
+        assert_eq!(parse_version_id("_1.5.2"), ("1.5.2", true));
+        assert_eq!(parse_version_id("1.5.2"), ("1.5.2", false));
+    }
+
+    #[test]
+    fn infers_java_version_from_release_time() {
+        assert_eq!(required_java_version("2013-04-25T15:45:00+00:00"), 8);
+        assert_eq!(required_java_version("2021-06-08T11:00:40+00:00"), 16);
+        assert_eq!(required_java_version("2023-06-12T13:25:51+00:00"), 17);
+        assert_eq!(required_java_version("2024-06-13T08:24:03+00:00"), 21);
+        assert_eq!(required_java_version("2026-01-13T12:00:00+00:00"), 25);
+        assert_eq!(required_java_version(""), 8);
+
+        // nothing installs java 16, those versions get the next LTS
+        let entry: VersionManifestEntry = serde_json::from_value(serde_json::json!({
+            "id": "21w19a",
+            "type": "snapshot",
+            "url": "",
+            "releaseTime": "2021-05-12T11:19:15+00:00"
+        }))
+        .unwrap();
+        let version: MinecraftVersionData = version_data(entry, None);
+        assert_eq!(version.java_version, 17);
+    }
+
+    #[test]
+    fn matches_april_fools_snapshots() {
+        let mut version: MinecraftVersionData =
+            version_data(find(&merged(), "2point0_red", false), None);
+
+        assert!(matches_type(&version, VersionType::AprilFools));
+        assert!(matches_type(&version, VersionType::OldAlpha));
+        assert!(!matches_type(&version, VersionType::Release));
+
+        version.id = String::from("1.20.1");
+        version.r#type = String::from("release");
+        assert!(!matches_type(&version, VersionType::AprilFools));
+        assert!(matches_type(&version, VersionType::Release));
     }
 }
@@ -5,32 +5,34 @@ use crate::{
     common::{
         minecraft::downloader::download_libraries,
         utils::{
-            directory::check_directory,
+            directory::get_data_root,
             file::{download_as_json, read_as_value, ChecksumType},
         },
     },
-    data::models::{BaseEventPayload, DownloadInstanceEventPayload},
+    data::models::{BaseEventPayload, DownloadInstanceEventPayload, FabricLoaderVersion},
 };
 
+use super::{fabric_meta::FABRIC_META, loader::ModLoader};
+
 pub async fn download_manifest(
     id: &str,
     fabric: &str,
 ) -> Result<Value, Box<dyn std::error::Error>> {
     let fabric: String = fabric.replace("fabric-", "");
 
-    let exists: std::path::PathBuf = check_directory("launcher/meta/net.fabricmc")
-        .await
-        .join(format!("{fabric}-{id}.json"));
-
-    if exists.exists() {
-        return read_as_value(&format!("launcher/meta/net.fabricmc/{fabric}-{id}.json")).await;
+    let profile_path: String = FABRIC_META.profile_path(id, &fabric);
+    if get_data_root().join(&profile_path).is_file() {
+        return read_as_value(&profile_path).await;
     }
 
+    // the meta serves a profile for any combination, even the ones the loader can't run
+    FABRIC_META.ensure_compatible(id, &fabric).await?;
+
     let manifest = download_as_json(
-        &format!("https://meta.fabricmc.net/v2/versions/loader/{id}/{fabric}/profile/json"),
+        &FABRIC_META.profile_url(id, &fabric),
         "",
         &ChecksumType::SHA1,
-        exists.to_str().unwrap(),
+        &profile_path,
         false,
         false,
         None,
@@ -47,7 +49,7 @@ pub async fn download_fabric(
     instance_name: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let fabric_version_manifest: Value =
-        read_as_value(&format!("launcher/meta/net.fabricmc/{fabric}-{id}.json")).await?;
+        read_as_value(&FABRIC_META.profile_path(id, fabric)).await?;
 
     app.emit_all(
         "download",
@@ -94,3 +96,52 @@ pub async fn download_fabric(
 
     Ok(())
 }
+
+pub struct Fabric;
+
+impl ModLoader for Fabric {
+    async fn list_versions(
+        &self,
+        game_version: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(FABRIC_META
+            .compatible_loaders(game_version)
+            .await?
+            .into_iter()
+            .map(|loader| loader.version)
+            .collect())
+    }
+
+    async fn latest_stable(
+        &self,
+        game_version: &str,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let loaders: Vec<FabricLoaderVersion> =
+            FABRIC_META.compatible_loaders(game_version).await?;
+        Ok(loaders
+            .iter()
+            .find(|loader| loader.stable)
+            .or(loaders.first())
+            .map(|loader| loader.version.clone()))
+    }
+
+    async fn resolve_manifest(
+        &self,
+        game_version: &str,
+        loader_version: &str,
+        _app: &AppHandle,
+        _instance_name: &str,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        download_manifest(game_version, loader_version).await
+    }
+
+    async fn install(
+        &self,
+        game_id: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        download_fabric(game_id, loader_version, app, instance_name).await
+    }
+}
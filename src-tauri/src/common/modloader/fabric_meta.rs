@@ -0,0 +1,186 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    common::utils::{cache::fetch_metadata_json, file::read_as_value},
+    data::{
+        constants::{
+            FABRIC_META_BASE, FABRIC_VERSION_MANIFEST, NET_FABRICMC_VERSION_MANIFEST,
+            ORG_QUILTMC_VERSION_MANIFEST, QUILT_META_BASE, QUILT_VERSION_MANIFEST,
+        },
+        models::{FabricGameVersion, FabricLoaderVersion},
+    },
+};
+
+/// Client for the Fabric meta v2 and its Quilt fork, the v3 meta. Both serve the same shapes,
+/// Quilt calls its intermediary mappings `hashed`.
+pub struct MetaClient {
+    name: &'static str,
+    base: &'static str,
+    api_version: &'static str,
+    manifest_url: &'static str,
+    manifest_path: &'static str,
+    meta_path: &'static str,
+}
+
+pub static FABRIC_META: MetaClient = MetaClient {
+    name: "Fabric",
+    base: FABRIC_META_BASE,
+    api_version: "v2",
+    manifest_url: FABRIC_VERSION_MANIFEST,
+    manifest_path: NET_FABRICMC_VERSION_MANIFEST,
+    meta_path: "launcher/meta/net.fabricmc",
+};
+
+pub static QUILT_META: MetaClient = MetaClient {
+    name: "Quilt",
+    base: QUILT_META_BASE,
+    api_version: "v3",
+    manifest_url: QUILT_VERSION_MANIFEST,
+    manifest_path: ORG_QUILTMC_VERSION_MANIFEST,
+    meta_path: "launcher/meta/org.quiltmc",
+};
+
+#[derive(Deserialize)]
+struct MetaEntry {
+    version: String,
+    #[serde(default)]
+    stable: Option<bool>,
+    #[serde(default)]
+    maven: String,
+}
+
+#[derive(Default, Deserialize)]
+struct VersionsManifest {
+    #[serde(default)]
+    game: Vec<MetaEntry>,
+    #[serde(default)]
+    loader: Vec<MetaEntry>,
+    #[serde(default)]
+    intermediary: Vec<MetaEntry>,
+    #[serde(default)]
+    hashed: Vec<MetaEntry>,
+}
+
+#[derive(Deserialize)]
+struct LoaderCompatibility {
+    loader: MetaEntry,
+}
+
+impl MetaEntry {
+    // quilt doesn't flag its loaders, its betas carry a suffix
+    fn into_loader_version(self) -> FabricLoaderVersion {
+        FabricLoaderVersion {
+            stable: self.stable.unwrap_or(!self.version.contains('-')),
+            version: self.version,
+            maven: self.maven,
+        }
+    }
+}
+
+impl MetaClient {
+    pub fn for_quilt(is_quilt: bool) -> &'static MetaClient {
+        if is_quilt {
+            &QUILT_META
+        } else {
+            &FABRIC_META
+        }
+    }
+
+    pub async fn download_versions(&self) -> Result<(), Box<dyn std::error::Error>> {
+        fetch_metadata_json(self.manifest_url, self.manifest_path, false).await?;
+        Ok(())
+    }
+
+    async fn versions_manifest(&self) -> Result<VersionsManifest, Box<dyn std::error::Error>> {
+        read_as_value(self.manifest_path).await
+    }
+
+    pub async fn game_versions(
+        &self,
+    ) -> Result<Vec<FabricGameVersion>, Box<dyn std::error::Error>> {
+        let manifest: VersionsManifest = self.versions_manifest().await?;
+        let mappings: Vec<&str> = manifest
+            .intermediary
+            .iter()
+            .chain(manifest.hashed.iter())
+            .map(|mapping| mapping.version.as_str())
+            .collect();
+
+        Ok(manifest
+            .game
+            .iter()
+            .map(|game| FabricGameVersion {
+                version: game.version.to_string(),
+                stable: game.stable.unwrap_or_default(),
+                intermediary: mappings.contains(&game.version.as_str()),
+            })
+            .collect())
+    }
+
+    /// Every loader build, whatever game versions it supports.
+    pub async fn loader_versions(
+        &self,
+    ) -> Result<Vec<FabricLoaderVersion>, Box<dyn std::error::Error>> {
+        Ok(self
+            .versions_manifest()
+            .await?
+            .loader
+            .into_iter()
+            .map(MetaEntry::into_loader_version)
+            .collect())
+    }
+
+    /// The loader builds the meta can build a profile for on `game_version`.
+    pub async fn compatible_loaders(
+        &self,
+        game_version: &str,
+    ) -> Result<Vec<FabricLoaderVersion>, Box<dyn std::error::Error>> {
+        let loaders: Value = fetch_metadata_json(
+            &format!(
+                "{}/{}/versions/loader/{game_version}",
+                self.base, self.api_version
+            ),
+            &format!("{}/loaders-{game_version}.json", self.meta_path),
+            false,
+        )
+        .await?;
+        let loaders: Vec<LoaderCompatibility> = serde_json::from_value(loaders)?;
+
+        Ok(loaders
+            .into_iter()
+            .map(|compatibility| compatibility.loader.into_loader_version())
+            .collect())
+    }
+
+    pub async fn ensure_compatible(
+        &self,
+        game_version: &str,
+        loader_version: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let compatible: bool = self
+            .compatible_loaders(game_version)
+            .await?
+            .iter()
+            .any(|loader| loader.version == loader_version);
+        if !compatible {
+            return Err(format!(
+                "{} loader {loader_version} doesn't support {game_version}",
+                self.name
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    pub fn profile_url(&self, game_version: &str, loader_version: &str) -> String {
+        format!(
+            "{}/{}/versions/loader/{game_version}/{loader_version}/profile/json",
+            self.base, self.api_version
+        )
+    }
+
+    pub fn profile_path(&self, game_version: &str, loader_version: &str) -> String {
+        format!("{}/{loader_version}-{game_version}.json", self.meta_path)
+    }
+}
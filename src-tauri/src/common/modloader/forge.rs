@@ -1,22 +1,30 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::io::{Cursor, BufReader, BufRead};
-use std::process::{Command, Stdio};
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
 
+use futures::{stream, StreamExt};
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
 use regex::Regex;
+use sha1::{Digest, Sha1};
 use tauri::{AppHandle, Manager};
 
-use crate::common::java::get_java_path::get_java_path;
+use crate::common::java::{self, get_java_path::get_java_path};
 use crate::common::minecraft::downloader::download_libraries;
-use crate::common::utils::directory::check_directory;
-use crate::common::utils::file::{self, download_as_vec, extract_file, read_as_value, write_value, library_name_to_path};
+use crate::common::minecraft::versions::{java_version, parse_version_id};
+use crate::common::utils::directory::{check_directory, get_data_root, safe_join};
+use crate::common::utils::file::{self, download_as_vec, extract_file, read_as_value, write_value, library_name_to_path, library_name_to_raw_path};
 use crate::common::utils::file::{
     download_as_json, merge_zips, read_as_vec, write_vec, ChecksumType,
 };
-use crate::data::constants::{FORGE_VERSION_MANFIEST, EXTRA_FORGE_VERSION_MANIFEST, NET_MINECRAFTFORGE_VERSION_MANIFEST};
-use crate::data::models::{DownloadInstanceEventPayload, BaseEventPayload};
+use crate::common::utils::log::write_line;
+use crate::common::utils::cache::{cached_modified_time, fetch_metadata, fetch_metadata_json};
+use crate::data::constants::{CLASSPATH_SEPARATOR, FORGE_VERSION_MANFIEST, EXTRA_FORGE_VERSION_MANIFEST, NET_MINECRAFTFORGE_VERSION_MANIFEST, NET_MINECRAFTFORGE_MAVEN_METADATA, NET_MINECRAFTFORGE_EXTRA_VERSION_MANIFEST, NET_MINECRAFTFORGE_PROMOTIONS, FORGE_PROMOTIONS, ATLAS_RESOURCES_BASE, FORGE_FILES_BASE, FORGE_MAVEN_BASE};
+use crate::data::models::{DownloadInstanceEventPayload, BaseEventPayload, ForgeBuild, ForgeInstallKind};
+
+use super::loader::{manifest_launch_contribution, LaunchContribution, ModLoader};
 
 #[derive(Serialize, Deserialize, Debug)]
 struct ForgeVersions {
@@ -30,12 +38,7 @@ pub async fn download_forge(
     instance_name: &str,
     app: &AppHandle
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let id: String = if id.starts_with("_") {
-        id.replace("_", "")
-    }
-    else {
-        id.to_string()
-    };
+    let id: String = parse_version_id(id).0.to_string();
     let forge: String = forge.replace("forge-", "");
     let forge_version_manifest: Value = read_as_value(&format!("launcher/meta/net.minecraftforge/{forge}.json")).await?;
     app.emit_all(
@@ -108,7 +111,13 @@ pub async fn download_forge(
         for extra in extras {
             let url: &str = extra["downloads"]["artifact"]["url"].as_str().unwrap();
             let sha1: &str = extra["downloads"]["artifact"]["sha1"].as_str().unwrap();
-            let path: String = extra["downloads"]["artifact"]["path"].as_str().unwrap().replace("${game_directory}", check_directory(&format!("instances/{instance_name}")).await.to_str().unwrap());
+            let path: &str = extra["downloads"]["artifact"]["path"].as_str().unwrap();
+            let instance_path: PathBuf = check_directory(&format!("instances/{instance_name}")).await;
+            let path: PathBuf = match path.strip_prefix("${game_directory}") {
+                Some(relative) => safe_join(&instance_path, relative.trim_start_matches(&['/', '\\']))?,
+                None => safe_join(&get_data_root(), path)?,
+            };
+            let path: &str = path.to_str().unwrap();
 
             download_as_vec(url, sha1, &ChecksumType::SHA1, &path, false, false, None).await?;
         }
@@ -135,74 +144,91 @@ pub async fn download_forge(
 
     if forge_install_manifest.is_ok() && !forge_client_path.is_file() {
         let forge_install_manifest: Value = forge_install_manifest.unwrap();
-        let instance_name_copy: String = instance_name.to_string();
-        app.emit_all(
-            "download",
-            DownloadInstanceEventPayload {
-                base: BaseEventPayload {
-                    message: String::from("Downloading Forge libraries"),
-                    status: String::from("Loading"),
-                },
-                total: 0,
-                downloaded: 0,
-                name: instance_name.to_string(),
+        let cache_path: String = format!("launcher/cache/{forge}");
+        run_install_profile(&forge_install_manifest, &id, &cache_path, app, instance_name).await?;
+    }
+
+    Ok(())
+}
+
+/// Downloads the libraries of an installer `install_profile.json` and runs its client processors.
+/// `cache_path` is the prefix [`extract_installer`] stored the installer files with.
+pub async fn run_install_profile(
+    install_profile: &Value,
+    id: &str,
+    cache_path: &str,
+    app: &AppHandle,
+    instance_name: &str
+) -> Result<(), Box<dyn std::error::Error>> {
+    let instance_name_copy: String = instance_name.to_string();
+    app.emit_all(
+        "download",
+        DownloadInstanceEventPayload {
+            base: BaseEventPayload {
+                message: String::from("Downloading installer libraries"),
+                status: String::from("Loading"),
             },
-        )
-        .unwrap();
-        if let Some(libraries) = forge_install_manifest["libraries"].as_array() {
-
-            let id_copy: String = id.to_string();
-            let libraries_copy: Vec<Value> = libraries.clone();
-            let handle_copy = app.clone();
-            tauri::async_runtime::spawn(async move {
-                match download_libraries(
-                    &libraries_copy,
-                    &id_copy,
-                    false,
-                    &handle_copy,
-                    &instance_name_copy
-                )
-                .await
-                {
-                    Ok(_) => Ok(()),
-                    Err(err) => {
-                        return Err(Box::new(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            format!("{err}"),
-                        )) as Box<dyn std::error::Error + Send + Sync>);
-                    }
+            total: 0,
+            downloaded: 0,
+            name: instance_name.to_string(),
+        },
+    )
+    .unwrap();
+    if let Some(libraries) = install_profile["libraries"].as_array() {
+
+        let id_copy: String = id.to_string();
+        let libraries_copy: Vec<Value> = libraries.clone();
+        let handle_copy = app.clone();
+        tauri::async_runtime::spawn(async move {
+            match download_libraries(
+                &libraries_copy,
+                &id_copy,
+                false,
+                &handle_copy,
+                &instance_name_copy
+            )
+            .await
+            {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    return Err(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        format!("{err}"),
+                    )) as Box<dyn std::error::Error + Send + Sync>);
                 }
-            }).await?.unwrap();
-        }
-        app.emit_all(
-            "download",
-            DownloadInstanceEventPayload {
-                base: BaseEventPayload {
-                    message: String::from("Patching game files"),
-                    status: String::from("Loading"),
-                },
-                total: 0,
-                downloaded: 0,
-                name: instance_name.to_string(),
+            }
+        }).await?.unwrap();
+    }
+    app.emit_all(
+        "download",
+        DownloadInstanceEventPayload {
+            base: BaseEventPayload {
+                message: String::from("Patching game files"),
+                status: String::from("Loading"),
             },
-        )
-        .unwrap();
-        if let Some(processors) = forge_install_manifest["processors"].as_array() {
-            for processor in processors {
-                let mut must_process: bool = false;
-                if let Some(sides) = processor["sides"].as_array() {
-                    for side in sides {
-                        if side.as_str().unwrap() == "client" {
-                            must_process = true;
-                        }
+            total: 0,
+            downloaded: 0,
+            name: instance_name.to_string(),
+        },
+    )
+    .unwrap();
+    if let Some(processors) = install_profile["processors"].as_array() {
+        let java_version: u8 = processor_java_version(id).await;
+        java::downloader::download(java_version, app, instance_name).await?;
+        for processor in processors {
+            let mut must_process: bool = false;
+            if let Some(sides) = processor["sides"].as_array() {
+                for side in sides {
+                    if side.as_str().unwrap() == "client" {
+                        must_process = true;
                     }
                 }
-                else {
-                    must_process = true;
-                }
-                if must_process {
-                    process_processor(processor, &forge_install_manifest["data"], &id, &forge).await?;
-                }
+            }
+            else {
+                must_process = true;
+            }
+            if must_process {
+                process_processor(processor, &install_profile["data"], id, cache_path, java_version).await?;
             }
         }
     }
@@ -223,7 +249,7 @@ pub async fn download_manifest(forge: &str, app: &AppHandle, instance_name: &str
     let forge_copy: String = forge.clone();
     let forge_manifest: Result<Value, Box<dyn Error + Send + Sync>> = tauri::async_runtime::spawn(async move {
         match download_as_json(
-            &format!("https://github.com/J0R6IT0/AtlasLauncherResources/raw/main/meta/net.minecraftforge/{forge_copy}.json"), 
+            &format!("{ATLAS_RESOURCES_BASE}/meta/net.minecraftforge/{forge_copy}.json"), 
             "", 
             &ChecksumType::SHA1, 
             "", 
@@ -252,7 +278,7 @@ pub async fn download_manifest(forge: &str, app: &AppHandle, instance_name: &str
     let forge_copy: String = forge.clone();
     let md5: Result<String, Box<dyn Error + Send + Sync>> = tauri::async_runtime::spawn(async move {
         match download_as_json(
-            &format!("https://files.minecraftforge.net/net/minecraftforge/forge/{forge_copy}/meta.json"), 
+            &format!("{FORGE_FILES_BASE}/net/minecraftforge/forge/{forge_copy}/meta.json"), 
             "", &ChecksumType::SHA1, &format!("launcher/meta/net.minecraftforge/{forge_copy}-hashes.json"), 
             false, 
             false,
@@ -289,7 +315,7 @@ pub async fn download_manifest(forge: &str, app: &AppHandle, instance_name: &str
     let instance_name_copy: String = instance_name.to_string();
     let installer_bytes: Result<Vec<u8>, Box<dyn Error + Send + Sync>> = tauri::async_runtime::spawn(async move {
         match download_as_vec(
-            &format!("https://maven.minecraftforge.net/net/minecraftforge/forge/{forge_copy}/forge-{forge_copy}-installer.jar"),
+            &format!("{FORGE_MAVEN_BASE}/net/minecraftforge/forge/{forge_copy}/forge-{forge_copy}-installer.jar"),
             &md5.unwrap(),
             &ChecksumType::MD5,
             "",
@@ -322,18 +348,11 @@ pub async fn download_manifest(forge: &str, app: &AppHandle, instance_name: &str
     )
     .unwrap();
     if installer_bytes.is_ok() {
-        let installer_bytes: Vec<u8> = installer_bytes.unwrap();
-        let installer_bytes_copy: Vec<u8> = installer_bytes.clone();
-        let install_profile: Vec<u8> = extract_file(&mut Cursor::new(installer_bytes_copy) , "install_profile.json").await?;
-        let install_profile: Value = serde_json::from_slice(&install_profile)?;
-        let installer_bytes_copy: Vec<u8> = installer_bytes.clone();
-        let version: Vec<u8> = extract_file(&mut Cursor::new(installer_bytes_copy) , "version.json").await?;
-        let version: Value = serde_json::from_slice(&version)?;
-        write_value(&version, &format!("launcher/meta/net.minecraftforge/{forge}.json"))?;
-        write_value(&install_profile, &format!("launcher/meta/net.minecraftforge/{forge}-install.json"))?;
-        let client_lzma: Vec<u8> = extract_file(&mut Cursor::new(installer_bytes), "client.lzma").await?;
-        write_vec(&client_lzma, &format!("launcher/cache/{forge}-client.lzma"))?;
-        return Ok(version);
+        return extract_installer(
+            installer_bytes.unwrap(),
+            &format!("launcher/meta/net.minecraftforge/{forge}"),
+            &format!("launcher/cache/{forge}"),
+        ).await;
     }
 
 
@@ -344,90 +363,169 @@ pub async fn download_manifest(forge: &str, app: &AppHandle, instance_name: &str
 
 }
 
-async fn process_processor(processor: &Value, mappings: &Value, id: &str, forge: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let java_path: String = get_java_path(8).await;
-    let mut classpath: String = String::from("");
-    let mut main_class = String::from("");
-    
-    if let Some(classes) = processor["classpath"].as_array() {
-        for class in classes {
+/// Stores the version json of an installer jar as `{meta_path}.json` and its install profile as
+/// `{meta_path}-install.json`. The binary patches and the installer itself, which some processors
+/// read, are kept as `{cache_path}-client.lzma` and `{cache_path}-installer.jar`.
+pub async fn extract_installer(installer_bytes: Vec<u8>, meta_path: &str, cache_path: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    let install_profile: Vec<u8> = extract_file(&mut Cursor::new(installer_bytes.clone()), "install_profile.json").await?;
+    let install_profile: Value = serde_json::from_slice(&install_profile)?;
+    let version: Vec<u8> = extract_file(&mut Cursor::new(installer_bytes.clone()), "version.json").await?;
+    let version: Value = serde_json::from_slice(&version)?;
+    write_value(&version, &format!("{meta_path}.json"))?;
+    write_value(&install_profile, &format!("{meta_path}-install.json"))?;
+    let client_lzma: Vec<u8> = extract_file(&mut Cursor::new(installer_bytes.clone()), "client.lzma").await?;
+    write_vec(&client_lzma, &format!("{cache_path}-client.lzma"))?;
+    write_vec(&installer_bytes, &format!("{cache_path}-installer.jar"))?;
+    Ok(version)
+}
+
+async fn process_processor(processor: &Value, mappings: &Value, id: &str, cache_path: &str, java_version: u8) -> Result<(), Box<dyn std::error::Error>> {
+    let jar: &str = processor["jar"].as_str().ok_or("The processor has no jar")?;
 
-            let class: &str = &library_name_to_path(class.as_str().unwrap());
+    // installers list every processor even when a previous run already produced its files
+    let outputs: Vec<(String, String)> = processor_outputs(processor, mappings);
+    if !outputs.is_empty() && outputs_match(&outputs) {
+        write_line(&format!("Skipping processor {jar}, its outputs are up to date"));
+        return Ok(());
+    }
+
+    let java_path: String = get_java_path(java_version).await;
+    if java_path.is_empty() {
+        return Err(format!("Java {java_version} is not installed").into());
+    }
 
-            classpath += &class;
-            classpath += ";";
+    let mut classpath: Vec<String> = vec![];
+    if let Some(classes) = processor["classpath"].as_array() {
+        for class in classes {
+            classpath.push(library_name_to_path(class.as_str().unwrap_or_default()));
         }
     }
 
-   if let Some(jar) = processor["jar"].as_str() {
-        let class: &str = &library_name_to_path(jar);
-        main_class = extract_main_class(class).await?;
-        classpath += &class;
-   }
+    let jar_path: String = library_name_to_path(jar);
+    let main_class: String = extract_main_class(&jar_path).await?;
+    classpath.push(jar_path);
 
-   let mut args: Vec<String> = [].to_vec();
+    let mut args: Vec<String> = [].to_vec();
 
-   if let Some(processor_args) = processor["args"].as_array() {
+    if let Some(processor_args) = processor["args"].as_array() {
         for processor_arg in processor_args {
-            let processor_arg: String = processor_arg.as_str().unwrap()
+            let processor_arg: String = processor_arg.as_str().unwrap_or_default()
                 .replace("{SIDE}", "client")
-                .replace("{MINECRAFT_JAR}", check_directory("versions").await.join(format!("{id}.jar")).to_str().unwrap());
-            
+                .replace("{MINECRAFT_JAR}", check_directory("versions").await.join(format!("{id}.jar")).to_str().unwrap())
+                .replace("{MINECRAFT_VERSION}", id)
+                .replace("{ROOT}", get_data_root().to_str().unwrap())
+                .replace("{LIBRARY_DIR}", check_directory("libraries").await.to_str().unwrap())
+                .replace("{INSTALLER}", get_data_root().join(format!("{cache_path}-installer.jar")).to_str().unwrap());
+
             if processor_arg.starts_with("[") && processor_arg.ends_with("]") {
-                let processor_arg: String = processor_arg.replace("[", "").replace("]", "");
-                let path: String = library_name_to_path(&processor_arg);
-                args.push(path);
+                args.push(library_name_to_path(&processor_arg[1..processor_arg.len() - 1]));
                 continue;
             }
             if processor_arg.starts_with("{") && processor_arg.ends_with("}") {
-                let mut processor_arg: String = processor_arg.replace("{", "").replace("}", "");
+                let mut processor_arg: String = processor_arg[1..processor_arg.len() - 1].to_string();
                 if let Some(mapping) = mappings[&processor_arg]["client"].as_str() {
                     if mapping.starts_with("[") && mapping.ends_with("]") {
-                        let mapping = mapping.replace("[", "").replace("]", "");
-                        processor_arg = library_name_to_path(&mapping);
+                        processor_arg = library_name_to_path(&mapping[1..mapping.len() - 1]);
                     }
                     else if mapping.contains("client.lzma") {
-                        processor_arg = check_directory("launcher/cache").await.join(format!("{forge}-client.lzma")).to_str().unwrap().to_string();
+                        processor_arg = get_data_root().join(format!("{cache_path}-client.lzma")).to_str().unwrap().to_string();
+                    }
+                    else if mapping.starts_with("'") && mapping.ends_with("'") {
+                        processor_arg = mapping[1..mapping.len() - 1].to_string();
                     }
                 }
                 args.push(processor_arg);
                 continue;
             }
 
-            args.push(processor_arg.to_string())
+            args.push(processor_arg)
         }
-   }
-
-   println!("{classpath}");
-   println!("{main_class}");
-   println!("{:?}", args);
+    }
 
-   let mut process: std::process::Child = Command::new(java_path)
-    .arg("-cp")
-    .arg(classpath)
-    .arg(main_class.trim())
-    .args(args)
-    // .creation_flags(CREATE_NO_WINDOW)
-    .stdout(Stdio::piped())
-    .spawn()
-    .expect("failed to execute java process");
+    write_line(&format!("Running processor {main_class} with Java {java_version}"));
 
-    let output: &mut std::process::ChildStdout = process.stdout.as_mut().unwrap();
-    let reader: BufReader<&mut std::process::ChildStdout> = BufReader::new(output);
-    let lines: std::io::Lines<BufReader<&mut std::process::ChildStdout>> = reader.lines();
+    let output: Output = Command::new(java_path)
+        .arg("-cp")
+        .arg(classpath.join(CLASSPATH_SEPARATOR))
+        .arg(main_class.trim())
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()?;
 
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        write_line(line);
+    }
 
-    for line in lines {
-        let line: String = line.unwrap();
-        println!("{line}");
+    if !output.status.success() {
+        let stderr: String = String::from_utf8_lossy(&output.stderr).to_string();
+        write_line(&stderr);
+        return Err(format!(
+            "The processor {jar} failed ({}): {}",
+            output.status,
+            stderr.lines().last().unwrap_or_default()
+        ).into());
     }
 
-   let status: i32 = process.wait().unwrap().code().unwrap();
-   println!("{status}");
+    for (output, sha1) in outputs.iter() {
+        if !output_matches(output, sha1) {
+            return Err(format!("The processor {jar} produced an invalid {output}").into());
+        }
+    }
 
     Ok(())
 }
 
+fn outputs_match(outputs: &[(String, String)]) -> bool {
+    outputs.iter().all(|(output, sha1)| output_matches(output, sha1))
+}
+
+fn output_matches(output: &str, sha1: &str) -> bool {
+    match std::fs::read(get_data_root().join(output)) {
+        Ok(bytes) => format!("{:x}", Sha1::digest(&bytes)).eq_ignore_ascii_case(sha1),
+        Err(_) => false,
+    }
+}
+
+/// The Java version the game itself runs on, processors are built for the same runtime.
+pub async fn processor_java_version(id: &str) -> u8 {
+    match read_as_value::<Value>(&format!("launcher/meta/net.minecraft/{id}.json")).await {
+        Ok(version) => java_version(&version),
+        Err(_) => 8,
+    }
+}
+
+/// Resolves the `outputs` of an install profile processor to library paths and their expected sha1.
+pub fn processor_outputs(processor: &Value, mappings: &Value) -> Vec<(String, String)> {
+    let mut outputs: Vec<(String, String)> = vec![];
+    if let Some(processor_outputs) = processor["outputs"].as_object() {
+        for (output, hash) in processor_outputs {
+            let output: Option<String> = resolve_mapping(output, mappings);
+            let hash: Option<String> = resolve_mapping(hash.as_str().unwrap_or_default(), mappings);
+            if let (Some(output), Some(hash)) = (output, hash) {
+                outputs.push((output, hash));
+            }
+        }
+    }
+    outputs
+}
+
+// {KEY} -> data entry, [maven:coordinate] -> libraries path, 'literal' -> literal
+fn resolve_mapping(value: &str, mappings: &Value) -> Option<String> {
+    let value: String = if value.starts_with('{') && value.ends_with('}') {
+        mappings[&value[1..value.len() - 1]]["client"].as_str()?.to_string()
+    } else {
+        value.to_string()
+    };
+    if value.starts_with('[') && value.ends_with(']') {
+        return Some(format!("libraries/{}", library_name_to_raw_path(&value[1..value.len() - 1])));
+    }
+    if value.starts_with('\'') && value.ends_with('\'') {
+        return Some(value[1..value.len() - 1].to_string());
+    }
+    Some(value)
+}
+
 async fn extract_main_class(class: &str) -> Result<String, Box<dyn std::error::Error>> {
     let bytes: Vec<u8> = read_as_vec(class).await?;
     let manifest: Vec<u8> = extract_file(&mut Cursor::new(bytes), "MANIFEST.MF").await?;
@@ -444,16 +542,9 @@ async fn extract_main_class(class: &str) -> Result<String, Box<dyn std::error::E
 }
 
 pub async fn download_versions() -> Result<(), Box<dyn std::error::Error>> {
-    let mut forge_manifest: String = file::download_as_string(
-        FORGE_VERSION_MANFIEST,
-        "",
-        &file::ChecksumType::SHA1,
-        "",
-        false,
-        true,
-        None,
-    )
-    .await?;
+    let mut forge_manifest: String = String::from_utf8(
+        fetch_metadata(FORGE_VERSION_MANFIEST, NET_MINECRAFTFORGE_MAVEN_METADATA, false).await?,
+    )?;
 
     forge_manifest = forge_manifest
         .replace("{", "[{")
@@ -464,14 +555,10 @@ pub async fn download_versions() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut final_forge_manifest: Vec<ForgeVersions> = serde_json::from_str(&forge_manifest)?;
 
-    let extra_forge_versions = file::download_as_json(
+    let extra_forge_versions = fetch_metadata_json(
         EXTRA_FORGE_VERSION_MANIFEST,
-        "",
-        &file::ChecksumType::SHA1,
-        "",
-        false,
+        NET_MINECRAFTFORGE_EXTRA_VERSION_MANIFEST,
         false,
-        None,
     )
     .await?;
 
@@ -508,5 +595,201 @@ pub async fn download_versions() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     write_value(&final_forge_manifest, NET_MINECRAFTFORGE_VERSION_MANIFEST)?;
+
+    // the builds are still usable without the promotions
+    if let Err(err) = fetch_metadata_json(FORGE_PROMOTIONS, NET_MINECRAFTFORGE_PROMOTIONS, false).await {
+        write_line(&format!("Failed to download the Forge promotions: {err}"));
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
+
+/// The Forge builds for `game_version`, newest first, flagged with the promotions the Forge site shows
+/// and dated.
+pub async fn get_builds(game_version: &str) -> Result<Vec<ForgeBuild>, Box<dyn std::error::Error>> {
+    let mut builds: Vec<ForgeBuild> = list_builds(game_version).await?;
+
+    let undated: Vec<String> = builds
+        .iter()
+        .filter(|build| build.release_time.is_none())
+        .map(|build| build.version.clone())
+        .collect();
+    let mut release_times: HashMap<String, String> = stream::iter(undated.into_iter().map(|version: String| async move {
+        let release_time: Option<String> = build_release_time(&version).await;
+        (version, release_time)
+    }))
+    .buffer_unordered(50)
+    .filter_map(|(version, release_time): (String, Option<String>)| async move {
+        release_time.map(|release_time| (version, release_time))
+    })
+    .collect()
+    .await;
+
+    for build in builds.iter_mut() {
+        if build.release_time.is_none() {
+            build.release_time = release_times.remove(&build.version);
+        }
+    }
+    Ok(builds)
+}
+
+// the per-build metadata doesn't change once published, it is only fetched while its date is unknown
+async fn build_release_time(version: &str) -> Option<String> {
+    let url: String = format!("{FORGE_FILES_BASE}/net/minecraftforge/forge/{version}/meta.json");
+    if let Some(release_time) = cached_modified_time(&url) {
+        return Some(release_time);
+    }
+    fetch_metadata(&url, &format!("launcher/meta/net.minecraftforge/{version}-hashes.json"), false)
+        .await
+        .ok()?;
+    cached_modified_time(&url)
+}
+
+// the builds without fetching anything per build, dated only when their version json is on disk
+async fn list_builds(game_version: &str) -> Result<Vec<ForgeBuild>, Box<dyn std::error::Error>> {
+    let versions: Vec<String> = Forge.list_versions(game_version).await?;
+
+    let promotions: Value = read_as_value(NET_MINECRAFTFORGE_PROMOTIONS).await.unwrap_or_default();
+    let recommended: Option<&str> = promotions["promos"][format!("{game_version}-recommended")].as_str();
+    let latest: Option<&str> = promotions["promos"][format!("{game_version}-latest")].as_str();
+
+    // builds only listed in the extra manifest predate the installer
+    let extra_forge_versions: Value = read_as_value(NET_MINECRAFTFORGE_EXTRA_VERSION_MANIFEST).await.unwrap_or_default();
+    let patch_versions: HashSet<String> = extra_forge_versions
+        .as_array()
+        .unwrap_or(&vec![])
+        .iter()
+        .filter(|entry| entry["mc_id"].as_str() == Some(game_version))
+        .flat_map(|entry| entry["versions"].as_array().cloned().unwrap_or_default())
+        .filter_map(|version| version["id"].as_str().map(|id| id.to_string()))
+        .collect();
+
+    let mut builds: Vec<ForgeBuild> = vec![];
+    for version in versions.into_iter().rev() {
+        let build: &str = build_number(&version, game_version);
+
+        let install_kind: ForgeInstallKind = if patch_versions.contains(&version) {
+            ForgeInstallKind::Patches
+        } else if uses_processors(game_version) {
+            ForgeInstallKind::Processors
+        } else {
+            ForgeInstallKind::Universal
+        };
+
+        let manifest_path: String = format!("launcher/meta/net.minecraftforge/{version}.json");
+        let release_time: Option<String> = if get_data_root().join(&manifest_path).is_file() {
+            read_as_value::<Value>(&manifest_path)
+                .await
+                .ok()
+                .and_then(|manifest| manifest["releaseTime"].as_str().map(|time| time.to_string()))
+        } else {
+            None
+        };
+
+        builds.push(ForgeBuild {
+            recommended: recommended == Some(build),
+            latest: latest == Some(build),
+            game_version: game_version.to_string(),
+            release_time,
+            install_kind,
+            version,
+        });
+    }
+
+    Ok(builds)
+}
+
+/// The Forge build out of a maven version, `1.20.1-47.1.0` -> `47.1.0` and
+/// `1.7.10-10.13.4.1614-1.7.10` -> `10.13.4.1614`.
+pub fn build_number<'a>(version: &'a str, game_version: &str) -> &'a str {
+    let build: &str = version.strip_prefix(&format!("{game_version}-")).unwrap_or(version);
+    build.split('-').next().unwrap_or(build)
+}
+
+/// The maven version of a Forge build, modpack formats only carry the build number.
+pub async fn maven_version(game_version: &str, build: &str) -> Result<String, Box<dyn std::error::Error>> {
+    Forge
+        .list_versions(game_version)
+        .await?
+        .into_iter()
+        .find(|version| version == build || build_number(version, game_version) == build)
+        .ok_or_else(|| format!("Forge {build} was not found for {game_version}").into())
+}
+
+// Forge installs through processors since 1.13, versions that aren't `1.x` all came later
+fn uses_processors(game_version: &str) -> bool {
+    let number = |part: Option<&str>| -> Option<u32> {
+        part?.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+    };
+    let mut parts = parse_version_id(game_version).0.split('.');
+    match (number(parts.next()), number(parts.next())) {
+        (Some(1), Some(minor)) => minor >= 13,
+        (Some(1), None) => false,
+        _ => true,
+    }
+}
+
+pub struct Forge;
+
+impl ModLoader for Forge {
+    async fn list_versions(&self, game_version: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let manifest: Vec<ForgeVersions> = read_as_value(NET_MINECRAFTFORGE_VERSION_MANIFEST).await?;
+        Ok(manifest
+            .into_iter()
+            .find_map(|mut entry| entry.data.remove(game_version))
+            .unwrap_or_default())
+    }
+
+    async fn latest_stable(&self, game_version: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let builds: Vec<ForgeBuild> = list_builds(game_version).await?;
+        let build: Option<&ForgeBuild> = builds
+            .iter()
+            .find(|build| build.recommended)
+            .or_else(|| builds.iter().find(|build| build.latest))
+            .or_else(|| builds.first());
+        Ok(build.map(|build| build.version.clone()))
+    }
+
+    async fn resolve_manifest(
+        &self,
+        _game_version: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        download_manifest(loader_version, app, instance_name).await
+    }
+
+    async fn install(
+        &self,
+        game_id: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        download_forge(game_id, loader_version, instance_name, app).await
+    }
+
+    fn launch_contribution(&self, manifest: &Value, loader_version: &str) -> LaunchContribution {
+        let mut contribution: LaunchContribution = manifest_launch_contribution(manifest);
+        // the old installers patch the client jar instead of adding libraries
+        contribution.version_jar = Some(format!("versions/forge-{loader_version}.jar"));
+        contribution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_install_kind_by_game_version() {
+        assert!(!uses_processors("1.12.2"));
+        assert!(!uses_processors("1.7.10"));
+        assert!(!uses_processors("1.7.10_pre4"));
+        assert!(uses_processors("1.13"));
+        assert!(uses_processors("1.20.1"));
+        assert!(uses_processors("26.1"));
+        assert_eq!(build_number("1.7.10-10.13.4.1614-1.7.10", "1.7.10"), "10.13.4.1614");
+    }
+}
@@ -0,0 +1,234 @@
+use serde_json::{json, Map, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    common::{
+        minecraft::{downloader::download_libraries, versions::parse_version_id},
+        utils::{
+            cache::fetch_metadata_json,
+            directory::get_data_root,
+            file::{read_as_value, write_value},
+        },
+    },
+    data::{
+        constants::{
+            COM_MUMFREY_LITELOADER_VERSION_MANIFEST, LITELOADER_BASE, LITELOADER_VERSION_MANIFEST,
+            MINECRAFT_LIBRARIES_BASE,
+        },
+        models::{BaseEventPayload, DownloadInstanceEventPayload},
+    },
+};
+
+use super::loader::ModLoader;
+
+/// A LiteLoader build as listed in its versions json.
+struct LiteLoaderBuild {
+    game_version: String,
+    build: Value,
+    repository: String,
+    snapshot: bool,
+}
+
+pub async fn download_versions() -> Result<(), Box<dyn std::error::Error>> {
+    fetch_metadata_json(
+        LITELOADER_VERSION_MANIFEST,
+        COM_MUMFREY_LITELOADER_VERSION_MANIFEST,
+        false,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Game versions LiteLoader has at least one build for.
+pub async fn get_game_versions() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let manifest: Value = read_as_value(COM_MUMFREY_LITELOADER_VERSION_MANIFEST).await?;
+    Ok(manifest["versions"]
+        .as_object()
+        .map(|versions| versions.keys().cloned().collect())
+        .unwrap_or_default())
+}
+
+// releases are listed under artefacts, builds from the snapshot repository under snapshots
+fn builds(game_entry: &Value) -> Vec<(&Value, bool)> {
+    let mut builds: Vec<(&Value, bool)> = vec![];
+    for (group, snapshot) in [("artefacts", false), ("snapshots", true)] {
+        if let Some(artefacts) = game_entry[group]["com.mumfrey:liteloader"].as_object() {
+            builds.extend(
+                artefacts
+                    .iter()
+                    .filter(|(key, _)| *key != "latest")
+                    .map(|(_, build)| (build, snapshot)),
+            );
+        }
+    }
+    builds
+}
+
+pub async fn get_versions(game_version: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let manifest: Value = read_as_value(COM_MUMFREY_LITELOADER_VERSION_MANIFEST).await?;
+    Ok(builds(&manifest["versions"][game_version])
+        .into_iter()
+        .filter_map(|(build, _)| build["version"].as_str())
+        .map(|version| version.to_string())
+        .collect())
+}
+
+async fn find_build(liteloader: &str) -> Result<LiteLoaderBuild, Box<dyn std::error::Error>> {
+    let manifest: Value = read_as_value(COM_MUMFREY_LITELOADER_VERSION_MANIFEST).await?;
+    let versions: &Map<String, Value> = manifest["versions"]
+        .as_object()
+        .ok_or("Invalid LiteLoader version manifest")?;
+
+    for (game_version, game_entry) in versions {
+        for (build, snapshot) in builds(game_entry) {
+            if build["version"].as_str() == Some(liteloader) {
+                return Ok(LiteLoaderBuild {
+                    game_version: game_version.to_string(),
+                    build: build.clone(),
+                    repository: game_entry["repo"]["url"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    snapshot,
+                });
+            }
+        }
+    }
+
+    Err(format!("LiteLoader {liteloader} was not found").into())
+}
+
+fn meta_path(liteloader: &str) -> String {
+    format!("launcher/meta/com.mumfrey.liteloader/{liteloader}.json")
+}
+
+/// Builds a launcher profile json out of the versions json entry, LiteLoader doesn't publish one.
+pub async fn download_manifest(liteloader: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    let meta_path: String = meta_path(liteloader);
+    if get_data_root().join(&meta_path).is_file() {
+        return read_as_value(&meta_path).await;
+    }
+
+    let LiteLoaderBuild {
+        game_version,
+        build,
+        repository,
+        snapshot,
+    } = find_build(liteloader).await?;
+
+    let file: &str = build["file"]
+        .as_str()
+        .ok_or("The LiteLoader build has no file")?;
+    let url: String = if snapshot {
+        format!("{repository}com/mumfrey/liteloader/{liteloader}/{file}")
+    } else {
+        format!("{LITELOADER_BASE}/versions/com/mumfrey/liteloader/{game_version}/{file}")
+    };
+
+    let mut libraries: Vec<Value> = vec![json!({
+        "name": format!("com.mumfrey:liteloader:{liteloader}"),
+        "downloads": {
+            "artifact": {
+                "path": format!("com/mumfrey/liteloader/{liteloader}/liteloader-{liteloader}.jar"),
+                "url": url,
+                "sha1": "",
+            }
+        }
+    })];
+    // its dependencies only carry a name, they are hosted by Mojang
+    for library in build["libraries"].as_array().unwrap_or(&vec![]) {
+        let mut library: Value = library.clone();
+        if library["url"].as_str().is_none() {
+            library["url"] = Value::String(format!("{MINECRAFT_LIBRARIES_BASE}/"));
+        }
+        libraries.push(library);
+    }
+
+    let tweak_class: &str = build["tweakClass"]
+        .as_str()
+        .unwrap_or("com.mumfrey.liteloader.launch.LiteLoaderTweaker");
+
+    let manifest: Value = json!({
+        "id": format!("{game_version}-LiteLoader{liteloader}"),
+        "inheritsFrom": game_version,
+        "mainClass": "net.minecraft.launchwrapper.Launch",
+        "libraries": libraries,
+        "arguments": {
+            "game": ["--tweakClass", tweak_class],
+        },
+    });
+    write_value(&manifest, &meta_path)?;
+    Ok(manifest)
+}
+
+pub async fn download_liteloader(
+    id: &str,
+    liteloader: &str,
+    app: &AppHandle,
+    instance_name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest: Value = download_manifest(liteloader).await?;
+
+    app.emit_all(
+        "download",
+        DownloadInstanceEventPayload {
+            base: BaseEventPayload {
+                message: String::from("Downloading LiteLoader libraries"),
+                status: String::from("Loading"),
+            },
+            total: 0,
+            downloaded: 0,
+            name: instance_name.to_string(),
+        },
+    )
+    .unwrap();
+
+    if let Some(libraries) = manifest["libraries"].as_array() {
+        download_libraries(libraries, parse_version_id(id).0, false, app, instance_name).await?;
+    }
+
+    Ok(())
+}
+
+pub struct LiteLoader;
+
+impl ModLoader for LiteLoader {
+    async fn list_versions(
+        &self,
+        game_version: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        get_versions(game_version).await
+    }
+
+    async fn latest_stable(
+        &self,
+        game_version: &str,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let versions: Vec<String> = self.list_versions(game_version).await?;
+        Ok(versions
+            .iter()
+            .find(|version| !version.ends_with("-SNAPSHOT"))
+            .or(versions.first())
+            .cloned())
+    }
+
+    async fn resolve_manifest(
+        &self,
+        _game_version: &str,
+        loader_version: &str,
+        _app: &AppHandle,
+        _instance_name: &str,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        download_manifest(loader_version).await
+    }
+
+    async fn install(
+        &self,
+        game_id: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        download_liteloader(game_id, loader_version, app, instance_name).await
+    }
+}
@@ -0,0 +1,345 @@
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::data::models::{InstanceModLoader, ModLoaderType};
+
+use super::{
+    fabric::Fabric, forge::Forge, liteloader::LiteLoader, neoforge::NeoForge, optifine::OptiFine,
+    quilt::Quilt,
+};
+
+/// What a loader adds on top of the vanilla launch arguments.
+#[derive(Default)]
+pub struct LaunchContribution {
+    pub main_class: Option<String>,
+    pub game_arguments: Vec<String>,
+    pub jvm_arguments: Vec<String>,
+    pub libraries: Vec<Value>,
+    pub classpath_ignore: Vec<String>,
+    /// Jar launched instead of the vanilla client jar when it exists, relative to the data root.
+    pub version_jar: Option<String>,
+    /// Launchwrapper tweakers, ordered by [`tweak_arguments`] once every loader contributed.
+    pub tweak_classes: Vec<String>,
+}
+
+impl LaunchContribution {
+    /// Stacks an addon loader on top, the main class of the base loader wins.
+    pub fn merge(&mut self, addon: LaunchContribution) {
+        if self.main_class.is_none() {
+            self.main_class = addon.main_class;
+        }
+        self.game_arguments.extend(addon.game_arguments);
+        self.jvm_arguments.extend(addon.jvm_arguments);
+        self.libraries.extend(addon.libraries);
+        self.classpath_ignore.extend(addon.classpath_ignore);
+        self.tweak_classes.extend(addon.tweak_classes);
+    }
+}
+
+pub trait ModLoader {
+    /// Loader versions that can be installed on top of `game_version`.
+    async fn list_versions(
+        &self,
+        game_version: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>>;
+
+    /// The build to suggest for `game_version`, the newest one the loader marks as stable.
+    async fn latest_stable(
+        &self,
+        game_version: &str,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>>;
+
+    /// Returns the loader version json, downloading it when it isn't cached yet.
+    async fn resolve_manifest(
+        &self,
+        game_version: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<Value, Box<dyn std::error::Error>>;
+
+    /// Downloads the loader libraries and runs its install steps. `game_id` is the version
+    /// the loader manifest inherits from, which has to be downloaded already.
+    async fn install(
+        &self,
+        game_id: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    fn launch_contribution(&self, manifest: &Value, _loader_version: &str) -> LaunchContribution {
+        manifest_launch_contribution(manifest)
+    }
+}
+
+/// Binds `$loader` to the implementation of `$loader_type`. `async fn`s keep [`ModLoader`] from
+/// being a trait object, so this is the one place that maps a loader type to its code.
+macro_rules! dispatch {
+    ($loader_type:expr, $loader:ident => $call:expr) => {
+        match $loader_type {
+            ModLoaderType::Forge => {
+                let $loader = Forge;
+                $call
+            }
+            ModLoaderType::NeoForge => {
+                let $loader = NeoForge;
+                $call
+            }
+            ModLoaderType::Fabric => {
+                let $loader = Fabric;
+                $call
+            }
+            ModLoaderType::Quilt => {
+                let $loader = Quilt;
+                $call
+            }
+            ModLoaderType::OptiFine => {
+                let $loader = OptiFine;
+                $call
+            }
+            ModLoaderType::LiteLoader => {
+                let $loader = LiteLoader;
+                $call
+            }
+        }
+    };
+}
+
+impl ModLoader for ModLoaderType {
+    async fn list_versions(
+        &self,
+        game_version: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        dispatch!(self, loader => loader.list_versions(game_version).await)
+    }
+
+    async fn latest_stable(
+        &self,
+        game_version: &str,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        dispatch!(self, loader => loader.latest_stable(game_version).await)
+    }
+
+    async fn resolve_manifest(
+        &self,
+        game_version: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        dispatch!(self, loader => {
+            loader
+                .resolve_manifest(game_version, loader_version, app, instance_name)
+                .await
+        })
+    }
+
+    async fn install(
+        &self,
+        game_id: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        dispatch!(self, loader => {
+            loader
+                .install(game_id, loader_version, app, instance_name)
+                .await
+        })
+    }
+
+    fn launch_contribution(&self, manifest: &Value, loader_version: &str) -> LaunchContribution {
+        dispatch!(self, loader => loader.launch_contribution(manifest, loader_version))
+    }
+}
+
+pub async fn list_versions(
+    loader_type: ModLoaderType,
+    game_version: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    loader_type.list_versions(game_version).await
+}
+
+pub async fn latest_stable(
+    loader_type: ModLoaderType,
+    game_version: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    loader_type.latest_stable(game_version).await
+}
+
+/// The loader version json of an instance, `None` for vanilla instances.
+pub async fn resolve_manifest(
+    modloader: &InstanceModLoader,
+    game_version: &str,
+    app: &AppHandle,
+    instance_name: &str,
+) -> Result<Option<Value>, Box<dyn std::error::Error>> {
+    let loader_type: ModLoaderType = match modloader.loader_type() {
+        Some(loader_type) => loader_type,
+        None => return Ok(None),
+    };
+    let manifest: Value = loader_type
+        .resolve_manifest(game_version, modloader.version(), app, instance_name)
+        .await?;
+    Ok(Some(manifest))
+}
+
+pub async fn install(
+    modloader: &InstanceModLoader,
+    game_id: &str,
+    app: &AppHandle,
+    instance_name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match modloader.loader_type() {
+        Some(loader_type) => {
+            loader_type
+                .install(game_id, modloader.version(), app, instance_name)
+                .await
+        }
+        None => Ok(()),
+    }
+}
+
+pub fn launch_contribution(modloader: &InstanceModLoader, manifest: &Value) -> LaunchContribution {
+    match modloader.loader_type() {
+        Some(loader_type) => loader_type.launch_contribution(manifest, modloader.version()),
+        None => LaunchContribution::default(),
+    }
+}
+
+/// The game version a loader manifest builds on.
+pub fn inherits_from(manifest: &Value) -> Result<String, Box<dyn std::error::Error>> {
+    match manifest["inheritsFrom"].as_str() {
+        Some(id) => Ok(id.to_string()),
+        None => Err("The modloader manifest doesn't inherit from any version".into()),
+    }
+}
+
+/// Every loader json follows the launcher profile format, only the extras differ.
+pub fn manifest_launch_contribution(manifest: &Value) -> LaunchContribution {
+    let strings = |value: &Value| -> Vec<String> {
+        value
+            .as_array()
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_str())
+                    .map(|value| value.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut game_arguments: Vec<String> = strings(&manifest["arguments"]["game"]);
+    let mut tweak_classes: Vec<String> = take_tweak_classes(&mut game_arguments);
+    // legacy jsons replace the whole argument string, only their tweakers are of interest
+    if let Some(minecraft_arguments) = manifest["minecraftArguments"].as_str() {
+        let mut minecraft_arguments: Vec<String> = minecraft_arguments
+            .split_whitespace()
+            .map(|argument| argument.to_string())
+            .collect();
+        tweak_classes.extend(take_tweak_classes(&mut minecraft_arguments));
+    }
+
+    LaunchContribution {
+        main_class: manifest["mainClass"]
+            .as_str()
+            .map(|value| value.to_string()),
+        game_arguments,
+        jvm_arguments: strings(&manifest["arguments"]["jvm"]),
+        libraries: manifest["libraries"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default(),
+        classpath_ignore: strings(&manifest["arguments"]["cp_ignore"]),
+        version_jar: None,
+        tweak_classes,
+    }
+}
+
+/// Removes every `--tweakClass <class>` pair from `arguments` and returns the classes.
+pub fn take_tweak_classes(arguments: &mut Vec<String>) -> Vec<String> {
+    let mut tweak_classes: Vec<String> = vec![];
+    let mut remaining: Vec<String> = vec![];
+    let mut iter = arguments.drain(..);
+    while let Some(argument) = iter.next() {
+        if argument == "--tweakClass" {
+            if let Some(tweak_class) = iter.next() {
+                tweak_classes.push(tweak_class);
+            }
+        } else {
+            remaining.push(argument);
+        }
+    }
+    drop(iter);
+    *arguments = remaining;
+    tweak_classes
+}
+
+/// The `--tweakClass` arguments for every tweaker once. Launchwrapper lets the first tweaker pick
+/// the launch target, so FML has to lead, LiteLoader follows and OptiFine goes last.
+pub fn tweak_arguments(tweak_classes: Vec<String>) -> Vec<String> {
+    let mut ordered: Vec<String> = vec![];
+    for tweak_class in tweak_classes {
+        if !ordered.contains(&tweak_class) {
+            ordered.push(tweak_class);
+        }
+    }
+    ordered.sort_by_key(|tweak_class| tweak_priority(tweak_class));
+
+    ordered
+        .into_iter()
+        .flat_map(|tweak_class| [String::from("--tweakClass"), tweak_class])
+        .collect()
+}
+
+fn tweak_priority(tweak_class: &str) -> u8 {
+    if tweak_class.contains(".fml.") {
+        0
+    } else if tweak_class.starts_with("com.mumfrey.liteloader") {
+        1
+    } else if tweak_class.starts_with("optifine.") {
+        3
+    } else {
+        2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn tweakers_are_stacked_in_launchwrapper_order() {
+        let mut arguments: Vec<String> = strings(&[
+            "--username",
+            "${auth_player_name}",
+            "--tweakClass",
+            "optifine.OptiFineTweaker",
+        ]);
+        let mut tweak_classes: Vec<String> = take_tweak_classes(&mut arguments);
+        assert_eq!(arguments, strings(&["--username", "${auth_player_name}"]));
+
+        tweak_classes.extend(strings(&[
+            "com.mumfrey.liteloader.launch.LiteLoaderTweaker",
+            "cpw.mods.fml.common.launcher.FMLTweaker",
+            "optifine.OptiFineTweaker",
+        ]));
+        assert_eq!(
+            tweak_arguments(tweak_classes),
+            strings(&[
+                "--tweakClass",
+                "cpw.mods.fml.common.launcher.FMLTweaker",
+                "--tweakClass",
+                "com.mumfrey.liteloader.launch.LiteLoaderTweaker",
+                "--tweakClass",
+                "optifine.OptiFineTweaker",
+            ])
+        );
+    }
+}
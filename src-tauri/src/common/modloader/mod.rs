@@ -1,4 +1,8 @@
 pub mod forge;
 pub mod fabric;
-pub mod quilt;
-pub mod util;
\ No newline at end of file
+pub mod fabric_meta;
+pub mod liteloader;
+pub mod loader;
+pub mod neoforge;
+pub mod optifine;
+pub mod quilt;
\ No newline at end of file
@@ -0,0 +1,195 @@
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::{
+    common::{
+        minecraft::{downloader::download_libraries, versions::parse_version_id},
+        utils::{
+            cache::fetch_metadata_json,
+            directory::get_data_root,
+            events::emit_progress,
+            file::{download_as_vec, read_as_value, ChecksumType},
+        },
+    },
+    data::constants::{
+        NEOFORGE_MAVEN_BASE, NEOFORGE_VERSION_MANIFEST, NET_NEOFORGED_VERSION_MANIFEST,
+    },
+};
+
+use super::{
+    forge::{extract_installer, run_install_profile},
+    loader::ModLoader,
+};
+
+pub async fn download_versions() -> Result<(), Box<dyn std::error::Error>> {
+    fetch_metadata_json(
+        NEOFORGE_VERSION_MANIFEST,
+        NET_NEOFORGED_VERSION_MANIFEST,
+        false,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Every NeoForge version listed in the maven metadata, newest first.
+pub async fn get_versions() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let manifest: Value = read_as_value(NET_NEOFORGED_VERSION_MANIFEST).await?;
+    let mut versions: Vec<String> = manifest["versions"]
+        .as_array()
+        .ok_or("Invalid NeoForge version manifest")?
+        .iter()
+        .filter_map(|version| version.as_str())
+        .map(|version| version.to_string())
+        .collect();
+    versions.reverse();
+    Ok(versions)
+}
+
+/// NeoForge versions start with the game version without the leading `1.`,
+/// `20.4.80` is built for 1.20.4 and `21.0.1` for 1.21.
+pub fn game_version(neoforge: &str) -> Option<String> {
+    let mut parts = neoforge.split('.');
+    let minor: u32 = parts.next()?.parse().ok()?;
+    let patch: u32 = parts.next()?.parse().ok()?;
+    if patch == 0 {
+        Some(format!("1.{minor}"))
+    } else {
+        Some(format!("1.{minor}.{patch}"))
+    }
+}
+
+fn meta_path(neoforge: &str) -> String {
+    format!("launcher/meta/net.neoforged/{neoforge}")
+}
+
+fn cache_path(neoforge: &str) -> String {
+    format!("launcher/cache/neoforge-{neoforge}")
+}
+
+/// The jar the installer processors produce, its presence means the version is installed.
+pub fn client_jar_path(neoforge: &str) -> String {
+    format!("libraries/net/neoforged/neoforge/{neoforge}/neoforge-{neoforge}-client.jar")
+}
+
+pub async fn download_manifest(
+    neoforge: &str,
+    app: &AppHandle,
+    instance_name: &str,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let meta_path: String = meta_path(neoforge);
+    if get_data_root().join(format!("{meta_path}.json")).is_file() {
+        return read_as_value(&format!("{meta_path}.json")).await;
+    }
+
+    emit_progress(app, instance_name, "Downloading NeoForge installer");
+
+    let installer_url: String = format!(
+        "{NEOFORGE_MAVEN_BASE}/releases/net/neoforged/neoforge/{neoforge}/neoforge-{neoforge}-installer.jar"
+    );
+    // the maven publishes the checksum next to every artifact
+    let sha1: Vec<u8> = download_as_vec(
+        &format!("{installer_url}.sha1"),
+        "",
+        &ChecksumType::SHA1,
+        "",
+        false,
+        true,
+        None,
+    )
+    .await?;
+    let sha1: String = String::from_utf8(sha1)?.trim().to_string();
+
+    let installer_bytes: Vec<u8> = download_as_vec(
+        &installer_url,
+        &sha1,
+        &ChecksumType::SHA1,
+        "",
+        false,
+        false,
+        Some((app, instance_name)),
+    )
+    .await?;
+
+    emit_progress(app, instance_name, "Extracting NeoForge installer");
+
+    extract_installer(installer_bytes, &meta_path, &cache_path(neoforge)).await
+}
+
+pub async fn download_neoforge(
+    id: &str,
+    neoforge: &str,
+    app: &AppHandle,
+    instance_name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let id: &str = parse_version_id(id).0;
+    let meta_path: String = meta_path(neoforge);
+    let neoforge_version_manifest: Value = read_as_value(&format!("{meta_path}.json")).await?;
+
+    emit_progress(app, instance_name, "Downloading NeoForge libraries");
+
+    if let Some(libraries) = neoforge_version_manifest["libraries"].as_array() {
+        download_libraries(libraries, id, false, app, instance_name).await?;
+    }
+
+    if get_data_root().join(client_jar_path(neoforge)).is_file() {
+        return Ok(());
+    }
+
+    let install_profile: Value = read_as_value(&format!("{meta_path}-install.json")).await?;
+    run_install_profile(
+        &install_profile,
+        id,
+        &cache_path(neoforge),
+        app,
+        instance_name,
+    )
+    .await
+}
+
+pub struct NeoForge;
+
+impl ModLoader for NeoForge {
+    async fn list_versions(
+        &self,
+        game_version: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(get_versions()
+            .await?
+            .into_iter()
+            .filter(|version| self::game_version(version).as_deref() == Some(game_version))
+            .collect())
+    }
+
+    async fn latest_stable(
+        &self,
+        game_version: &str,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        // betas carry a suffix, 20.4.80-beta
+        let versions: Vec<String> = self.list_versions(game_version).await?;
+        Ok(versions
+            .iter()
+            .find(|version| !version.contains('-'))
+            .or(versions.first())
+            .cloned())
+    }
+
+    async fn resolve_manifest(
+        &self,
+        _game_version: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        download_manifest(loader_version, app, instance_name).await
+    }
+
+    async fn install(
+        &self,
+        game_id: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        download_neoforge(game_id, loader_version, app, instance_name).await
+    }
+}
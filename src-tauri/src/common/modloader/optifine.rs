@@ -0,0 +1,337 @@
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+use regex::Regex;
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+use crate::{
+    common::{
+        java::{self, get_java_path::get_java_path},
+        minecraft::{
+            downloader::download_libraries, instance::game_path, versions::parse_version_id,
+        },
+        utils::{
+            directory::{check_directory, get_data_root},
+            events::emit_progress,
+            file::{extract_file, read_as_value, read_as_vec, write_value, write_vec},
+            log::write_line,
+        },
+    },
+    data::{
+        constants::MINECRAFT_LIBRARIES_BASE,
+        models::{InstanceInfo, InstanceModLoader},
+    },
+};
+
+use super::{forge::processor_java_version, loader::ModLoader};
+
+// OptiFine can't be downloaded automatically, the installers the user picked are kept here
+static INSTALLERS_PATH: &str = "launcher/optifine";
+
+static LAUNCHWRAPPER_SHA1: &str = "111e7bea9c968cdb3d06ef4632bf7ff0824d0f36";
+
+/// `OptiFine_1.12.2_HD_U_G5.jar` -> (`1.12.2`, `HD_U_G5`), previews keep their `preview_` prefix
+/// out of the edition.
+pub fn parse_installer_name(file_name: &str) -> Option<(String, String)> {
+    let re: Regex = Regex::new(r"OptiFine_([0-9.]+)_(HD\w*)\.jar$").unwrap();
+    let captures = re.captures(file_name)?;
+    Some((captures[1].to_string(), captures[2].to_string()))
+}
+
+/// The game version of an OptiFine version, `1.12.2_HD_U_G5` -> `1.12.2`.
+pub fn game_version(optifine: &str) -> Option<&str> {
+    optifine
+        .split_once("_HD")
+        .map(|(game_version, _)| game_version)
+}
+
+fn installer_path(optifine: &str) -> String {
+    format!("{INSTALLERS_PATH}/OptiFine_{optifine}.jar")
+}
+
+fn meta_path(optifine: &str) -> String {
+    format!("launcher/meta/optifine/{optifine}.json")
+}
+
+fn library_path(optifine: &str) -> String {
+    format!("optifine/OptiFine/{optifine}/OptiFine-{optifine}.jar")
+}
+
+/// Stores a user supplied OptiFine jar and returns its version.
+pub async fn import_installer(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let file_name: &str = Path::new(path)
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .ok_or("Invalid installer path")?;
+    let (game_version, edition) = parse_installer_name(file_name)
+        .ok_or(format!("{file_name} is not named like an OptiFine jar"))?;
+
+    let installer: Vec<u8> = fs::read(path)?;
+    if extract_file(&mut Cursor::new(installer.clone()), "Config.class")
+        .await
+        .is_err()
+    {
+        return Err(format!("{file_name} is not an OptiFine jar").into());
+    }
+
+    let optifine: String = format!("{game_version}_{edition}");
+    write_vec(&installer, &installer_path(&optifine))?;
+    Ok(optifine)
+}
+
+/// Every imported OptiFine version, newest first.
+pub fn get_versions() -> Vec<String> {
+    let mut versions: Vec<String> = match fs::read_dir(get_data_root().join(INSTALLERS_PATH)) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                let (game_version, edition) = parse_installer_name(entry.file_name().to_str()?)?;
+                Some(format!("{game_version}_{edition}"))
+            })
+            .collect(),
+        Err(_) => vec![],
+    };
+    versions.sort();
+    versions.reverse();
+    versions
+}
+
+// libraries the launcher produces itself, there is nothing to download
+fn local_library(name: &str, path: &str) -> Value {
+    json!({
+        "name": name,
+        "downloads": {
+            "artifact": {
+                "path": path,
+                "url": "",
+                "sha1": "",
+            }
+        }
+    })
+}
+
+pub async fn download_manifest(optifine: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    let meta_path: String = meta_path(optifine);
+    if get_data_root().join(&meta_path).is_file() {
+        return read_as_value(&meta_path).await;
+    }
+
+    let game_version: &str = game_version(optifine).ok_or("Invalid OptiFine version")?;
+    let installer: Vec<u8> = read_as_vec(&installer_path(optifine))
+        .await
+        .map_err(|_| format!("The OptiFine {optifine} jar has not been imported"))?;
+
+    // recent jars bundle a launchwrapper that works on newer java versions
+    let launchwrapper_version: Option<Vec<u8>> =
+        extract_file(&mut Cursor::new(installer.clone()), "launchwrapper-of.txt")
+            .await
+            .ok();
+    let launchwrapper: Value = match launchwrapper_version {
+        Some(launchwrapper_version) => {
+            let launchwrapper_version: String =
+                String::from_utf8(launchwrapper_version)?.trim().to_string();
+            let jar: Vec<u8> = extract_file(
+                &mut Cursor::new(installer),
+                &format!("launchwrapper-of-{launchwrapper_version}.jar"),
+            )
+            .await?;
+            let path: String = format!(
+                "optifine/launchwrapper-of/{launchwrapper_version}/launchwrapper-of-{launchwrapper_version}.jar"
+            );
+            write_vec(&jar, &format!("libraries/{path}"))?;
+            local_library(
+                &format!("optifine:launchwrapper-of:{launchwrapper_version}"),
+                &path,
+            )
+        }
+        None => json!({
+            "name": "net.minecraft:launchwrapper:1.12",
+            "downloads": {
+                "artifact": {
+                    "path": "net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar",
+                    "url": format!("{MINECRAFT_LIBRARIES_BASE}/net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar"),
+                    "sha1": LAUNCHWRAPPER_SHA1,
+                }
+            }
+        }),
+    };
+
+    let manifest: Value = json!({
+        "id": format!("{game_version}-OptiFine_{}", &optifine[game_version.len() + 1..]),
+        "inheritsFrom": game_version,
+        "mainClass": "net.minecraft.launchwrapper.Launch",
+        "libraries": [
+            local_library(&format!("optifine:OptiFine:{optifine}"), &library_path(optifine)),
+            launchwrapper,
+        ],
+        "arguments": {
+            "game": ["--tweakClass", "optifine.OptiFineTweaker"],
+        },
+    });
+    write_value(&manifest, &meta_path)?;
+    Ok(manifest)
+}
+
+pub async fn download_optifine(
+    id: &str,
+    optifine: &str,
+    app: &AppHandle,
+    instance_name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let id: &str = parse_version_id(id).0;
+    let manifest: Value = download_manifest(optifine).await?;
+
+    // the local libraries have to exist before the rest are downloaded
+    let library: PathBuf = get_data_root()
+        .join("libraries")
+        .join(library_path(optifine));
+    if !library.is_file() {
+        emit_progress(app, instance_name, "Patching OptiFine");
+        patch(id, optifine, &library, app, instance_name).await?;
+    }
+
+    if let Some(libraries) = manifest["libraries"].as_array() {
+        download_libraries(libraries, id, false, app, instance_name).await?;
+    }
+
+    Ok(())
+}
+
+// the patcher diffs the jar against the vanilla client and keeps only the OptiFine classes
+async fn patch(
+    id: &str,
+    optifine: &str,
+    library: &Path,
+    app: &AppHandle,
+    instance_name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let installer: PathBuf = get_data_root().join(installer_path(optifine));
+    check_directory(&format!("libraries/optifine/OptiFine/{optifine}")).await;
+
+    let installer_bytes: Vec<u8> = fs::read(&installer)?;
+    if extract_file(&mut Cursor::new(installer_bytes), "optifine/Patcher.class")
+        .await
+        .is_err()
+    {
+        // older jars are the library themselves
+        fs::copy(&installer, library)?;
+        return Ok(());
+    }
+
+    let java_version: u8 = processor_java_version(id).await;
+    java::downloader::download(java_version, app, instance_name).await?;
+    let java_path: String = get_java_path(java_version).await;
+
+    let output: Output = Command::new(java_path)
+        .arg("-cp")
+        .arg(&installer)
+        .arg("optifine.Patcher")
+        .arg(get_data_root().join(format!("versions/{id}.jar")))
+        .arg(&installer)
+        .arg(library)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()?;
+
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        write_line(line);
+    }
+
+    if !output.status.success() {
+        let stderr: String = String::from_utf8_lossy(&output.stderr).to_string();
+        write_line(&stderr);
+        let _ = fs::remove_file(library);
+        return Err(format!("The OptiFine patcher failed ({})", output.status).into());
+    }
+
+    Ok(())
+}
+
+/// Copies an imported OptiFine jar into the mods folder of a Forge instance, where it loads as a
+/// regular mod. Any other OptiFine jar in the folder is replaced.
+pub async fn install_as_mod(
+    instance_name: &str,
+    optifine: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let instance: InstanceInfo =
+        read_as_value(&format!("instances/{instance_name}/atlas_instance.json")).await?;
+    if !matches!(instance.modloader, InstanceModLoader::Forge(_)) {
+        return Err(format!("{instance_name} is not a Forge instance").into());
+    }
+    if game_version(optifine) != Some(parse_version_id(&instance.version).0) {
+        return Err(format!("OptiFine {optifine} is not built for {}", instance.version).into());
+    }
+
+    let installer: Vec<u8> = read_as_vec(&installer_path(optifine))
+        .await
+        .map_err(|_| format!("The OptiFine {optifine} jar has not been imported"))?;
+
+    // linked instances keep their mods in the game folder of the other launcher
+    let mods_path: PathBuf = game_path(&instance).join("mods");
+    fs::create_dir_all(&mods_path)?;
+    for entry in fs::read_dir(&mods_path)?.flatten() {
+        if let Some(file_name) = entry.file_name().to_str() {
+            if parse_installer_name(file_name).is_some() {
+                fs::remove_file(entry.path())?;
+            }
+        }
+    }
+
+    // copied under a temporary name first, a jar cut short would crash the game
+    let jar_name: String = format!("OptiFine_{optifine}.jar");
+    let temp_path: PathBuf = mods_path.join(format!(".{jar_name}.tmp"));
+    fs::write(&temp_path, &installer)?;
+    fs::rename(&temp_path, mods_path.join(jar_name))?;
+    Ok(())
+}
+
+pub struct OptiFine;
+
+impl ModLoader for OptiFine {
+    async fn list_versions(
+        &self,
+        game_version: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(get_versions()
+            .into_iter()
+            .filter(|version| self::game_version(version) == Some(game_version))
+            .collect())
+    }
+
+    async fn latest_stable(
+        &self,
+        game_version: &str,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let versions: Vec<String> = self.list_versions(game_version).await?;
+        Ok(versions
+            .iter()
+            .find(|version| !version.contains("_pre"))
+            .or(versions.first())
+            .cloned())
+    }
+
+    async fn resolve_manifest(
+        &self,
+        _game_version: &str,
+        loader_version: &str,
+        _app: &AppHandle,
+        _instance_name: &str,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        download_manifest(loader_version).await
+    }
+
+    async fn install(
+        &self,
+        game_id: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        download_optifine(game_id, loader_version, app, instance_name).await
+    }
+}
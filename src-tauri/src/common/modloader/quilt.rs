@@ -5,29 +5,31 @@ use crate::{
     common::{
         minecraft::downloader::download_libraries,
         utils::{
-            directory::check_directory,
+            directory::get_data_root,
             file::{download_as_json, read_as_value, ChecksumType},
         },
     },
-    data::models::{BaseEventPayload, DownloadInstanceEventPayload},
+    data::models::{BaseEventPayload, DownloadInstanceEventPayload, FabricLoaderVersion},
 };
 
+use super::{fabric_meta::QUILT_META, loader::ModLoader};
+
 pub async fn download_manifest(id: &str, quilt: &str) -> Result<Value, Box<dyn std::error::Error>> {
     let quilt: String = quilt.replace("quilt-", "");
 
-    let exists: std::path::PathBuf = check_directory("launcher/meta/org.quiltmc")
-        .await
-        .join(format!("{quilt}-{id}.json"));
-
-    if exists.exists() {
-        return read_as_value(&format!("launcher/meta/org.quiltmc/{quilt}-{id}.json")).await;
+    let profile_path: String = QUILT_META.profile_path(id, &quilt);
+    if get_data_root().join(&profile_path).is_file() {
+        return read_as_value(&profile_path).await;
     }
 
+    // the meta serves a profile for any combination, even the ones the loader can't run
+    QUILT_META.ensure_compatible(id, &quilt).await?;
+
     let manifest = download_as_json(
-        &format!("https://meta.quiltmc.org/v3/versions/loader/{id}/{quilt}/profile/json"),
+        &QUILT_META.profile_url(id, &quilt),
         "",
         &ChecksumType::SHA1,
-        exists.to_str().unwrap(),
+        &profile_path,
         false,
         false,
         None,
@@ -43,8 +45,7 @@ pub async fn download_quilt(
     app: &AppHandle,
     instance_name: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let quilt_version_manifest: Value =
-        read_as_value(&format!("launcher/meta/org.quiltmc/{quilt}-{id}.json")).await?;
+    let quilt_version_manifest: Value = read_as_value(&QUILT_META.profile_path(id, quilt)).await?;
 
     app.emit_all(
         "download",
@@ -91,3 +92,51 @@ pub async fn download_quilt(
 
     Ok(())
 }
+
+pub struct Quilt;
+
+impl ModLoader for Quilt {
+    async fn list_versions(
+        &self,
+        game_version: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(QUILT_META
+            .compatible_loaders(game_version)
+            .await?
+            .into_iter()
+            .map(|loader| loader.version)
+            .collect())
+    }
+
+    async fn latest_stable(
+        &self,
+        game_version: &str,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let loaders: Vec<FabricLoaderVersion> = QUILT_META.compatible_loaders(game_version).await?;
+        Ok(loaders
+            .iter()
+            .find(|loader| loader.stable)
+            .or(loaders.first())
+            .map(|loader| loader.version.clone()))
+    }
+
+    async fn resolve_manifest(
+        &self,
+        game_version: &str,
+        loader_version: &str,
+        _app: &AppHandle,
+        _instance_name: &str,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        download_manifest(game_version, loader_version).await
+    }
+
+    async fn install(
+        &self,
+        game_id: &str,
+        loader_version: &str,
+        app: &AppHandle,
+        instance_name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        download_quilt(game_id, loader_version, app, instance_name).await
+    }
+}
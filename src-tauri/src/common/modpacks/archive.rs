@@ -0,0 +1,127 @@
+use std::{
+    fs::{self, File},
+    io::{self, Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use tauri::AppHandle;
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    common::{
+        minecraft::instance::{game_path, install_instance},
+        utils::{
+            directory::{get_data_root, is_excluded, relative_files},
+            events::emit_progress,
+            file::read_as_value,
+        },
+    },
+    data::models::{InstanceArchiveOptions, InstanceInfo},
+};
+
+use super::import::{extract_folder, finish, new_instance_path};
+
+static INSTANCE_FILE: &str = "atlas_instance.json";
+
+/// Zips an instance with its config into `output`, so it can be imported on another machine.
+/// Linked instances are exported with the files of their game folder.
+pub async fn export(
+    instance_name: &str,
+    options: &InstanceArchiveOptions,
+    output: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut instance: InstanceInfo =
+        read_as_value(&format!("instances/{instance_name}/{INSTANCE_FILE}")).await?;
+    let instance_path: PathBuf = get_data_root().join(format!("instances/{instance_name}"));
+    let game_path: PathBuf = game_path(&instance);
+
+    // the images of a linked instance stay in the launcher folder
+    let mut files: Vec<(String, PathBuf)> = vec![];
+    if game_path != instance_path {
+        for image in [&instance.background, &instance.icon] {
+            if !image.starts_with("default") {
+                files.push((image.to_string(), instance_path.join(image)));
+            }
+        }
+    }
+    files.extend(
+        relative_files(&game_path)
+            .into_iter()
+            .filter(|path| path != INSTANCE_FILE && !is_excluded(path, &options.exclude))
+            .map(|path| {
+                let file: PathBuf = game_path.join(&path);
+                (path, file)
+            }),
+    );
+    instance.game_dir = None;
+
+    let mut archive: ZipWriter<File> = ZipWriter::new(File::create(output)?);
+    let file_options: FileOptions =
+        FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    archive.start_file(INSTANCE_FILE, file_options)?;
+    archive.write_all(&serde_json::to_vec_pretty(&instance)?)?;
+    for (path, file) in files {
+        archive.start_file(path, file_options)?;
+        io::copy(&mut File::open(file)?, &mut archive)?;
+    }
+
+    archive.finish()?;
+    Ok(())
+}
+
+fn read_instance(archive: &[u8]) -> Result<InstanceInfo, Box<dyn std::error::Error>> {
+    let mut archive: ZipArchive<Cursor<&[u8]>> = ZipArchive::new(Cursor::new(archive))?;
+    let mut contents: Vec<u8> = vec![];
+    archive
+        .by_name(INSTANCE_FILE)
+        .map_err(|_| format!("The archive is not an instance, it has no {INSTANCE_FILE}"))?
+        .read_to_end(&mut contents)?;
+    Ok(serde_json::from_slice(&contents)?)
+}
+
+/// Creates an instance out of an archive made by [`export`], reinstalling its version and
+/// loaders. The archived name is used unless `instance_name` is given.
+pub async fn import(
+    path: &str,
+    instance_name: Option<&str>,
+    app: &AppHandle,
+) -> Result<InstanceInfo, Box<dyn std::error::Error>> {
+    let archive: Vec<u8> = fs::read(path)?;
+    let mut instance: InstanceInfo = read_instance(&archive)?;
+    if let Some(instance_name) = instance_name.filter(|name| !name.trim().is_empty()) {
+        instance.name = instance_name.to_string();
+    }
+    instance.game_dir = None;
+    // the arguments run on this machine, only the memory settings are safe to carry over
+    instance
+        .jvm_args
+        .retain(|arg| arg.starts_with("-Xms") || arg.starts_with("-Xmx"));
+    // the images are file names inside the instance folder, anything else is reset
+    for image in [&mut instance.background, &mut instance.icon] {
+        if image.contains(['/', '\\']) || image.contains("..") {
+            *image = String::from("default0");
+        }
+    }
+
+    let instance_name: String = instance.name.to_string();
+    let instance_path: PathBuf = new_instance_path(&instance_name)?;
+    let result: Result<InstanceInfo, String> =
+        import_archive(&archive, instance, &instance_path, app)
+            .await
+            .map_err(|err| err.to_string());
+    finish(result, &instance_name, &instance_path, app)
+}
+
+async fn import_archive(
+    archive: &[u8],
+    instance: InstanceInfo,
+    instance_path: &Path,
+    app: &AppHandle,
+) -> Result<InstanceInfo, Box<dyn std::error::Error>> {
+    emit_progress(app, &instance.name, "Extracting the instance");
+    extract_folder(archive, "", instance_path)?;
+
+    install_instance(&instance, app).await?;
+    Ok(instance)
+}
@@ -0,0 +1,450 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+use futures::{stream, StreamExt};
+use reqwest::{header::USER_AGENT, Client, Response};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+use zip::ZipArchive;
+
+use crate::{
+    common::{
+        minecraft::instance::install_instance,
+        modloader::forge,
+        utils::{
+            directory::{get_data_root, safe_join},
+            events::emit_progress,
+            file::{download_as_vec, ChecksumType},
+            log::write_line,
+            mirror::candidate_urls,
+            network::ensure_online,
+            settings::get_settings,
+        },
+    },
+    data::{
+        constants::{CURSEFORGE_API_BASE, USER_AGENT as PROJECT_USER_AGENT},
+        models::{CurseForgeImportReport, CurseForgeMissingFile, InstanceInfo, InstanceModLoader},
+    },
+};
+
+use super::import::{extract_folder, finish, new_instance_path};
+
+// CurseForge hash algorithms
+static SHA1_ALGORITHM: u8 = 1;
+static MD5_ALGORITHM: u8 = 2;
+
+// CurseForge project classes that don't go to the mods folder
+static RESOURCE_PACK_CLASS: u64 = 12;
+static SHADER_PACK_CLASS: u64 = 6552;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    minecraft: ManifestMinecraft,
+    name: String,
+    files: Vec<ManifestFile>,
+    #[serde(default = "default_overrides")]
+    overrides: String,
+}
+
+fn default_overrides() -> String {
+    String::from("overrides")
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestMinecraft {
+    version: String,
+    #[serde(default)]
+    mod_loaders: Vec<ManifestModLoader>,
+}
+
+#[derive(Deserialize)]
+struct ManifestModLoader {
+    id: String,
+    #[serde(default)]
+    primary: bool,
+}
+
+#[derive(Deserialize)]
+struct ManifestFile {
+    #[serde(rename = "projectID")]
+    project_id: u64,
+    #[serde(rename = "fileID")]
+    file_id: u64,
+    #[serde(default = "default_required")]
+    required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeFile {
+    id: u64,
+    mod_id: u64,
+    file_name: String,
+    /// Missing when the author opted out of third party distribution.
+    #[serde(default)]
+    download_url: Option<String>,
+    #[serde(default)]
+    hashes: Vec<CurseForgeHash>,
+}
+
+#[derive(Deserialize)]
+struct CurseForgeHash {
+    value: String,
+    algo: u8,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeMod {
+    id: u64,
+    name: String,
+    #[serde(default)]
+    class_id: Option<u64>,
+    #[serde(default)]
+    links: CurseForgeLinks,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeLinks {
+    #[serde(default)]
+    website_url: Option<String>,
+}
+
+/// POSTs to the CurseForge api, through the configured mirror when there is one.
+async fn post<T: DeserializeOwned>(
+    path: &str,
+    body: &Value,
+) -> Result<T, Box<dyn std::error::Error>> {
+    ensure_online()?;
+
+    let api_key: String = get_settings().await.curseforge_api_key;
+    let client: Client = Client::new();
+
+    let mut last_error: Option<reqwest::Error> = None;
+    for candidate in candidate_urls(&format!("{CURSEFORGE_API_BASE}{path}")).await {
+        let response: Result<Response, reqwest::Error> = client
+            .post(&candidate)
+            .header(USER_AGENT, PROJECT_USER_AGENT)
+            .header("x-api-key", &api_key)
+            .json(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        // a mirror answering with something unexpected falls through to the next candidate
+        match response {
+            Ok(response) => match response.json::<ApiResponse<T>>().await {
+                Ok(response) => return Ok(response.data),
+                Err(err) => last_error = Some(err),
+            },
+            Err(err) => last_error = Some(err),
+        }
+    }
+
+    match last_error {
+        Some(err) => Err(Box::new(err)),
+        None => Err(format!("There is no url to reach {path} through").into()),
+    }
+}
+
+fn read_manifest(pack: &[u8]) -> Result<Manifest, Box<dyn std::error::Error>> {
+    let mut archive: ZipArchive<Cursor<&[u8]>> = ZipArchive::new(Cursor::new(pack))?;
+    let mut contents: Vec<u8> = vec![];
+    archive
+        .by_name("manifest.json")
+        .map_err(|_| "The zip is not a CurseForge modpack, it has no manifest.json")?
+        .read_to_end(&mut contents)?;
+    Ok(serde_json::from_slice(&contents)?)
+}
+
+/// `forge-47.1.0` -> (`forge`, `47.1.0`).
+fn split_modloader_id(id: &str) -> Result<(&str, &str), Box<dyn std::error::Error>> {
+    id.split_once('-')
+        .filter(|(_, version)| !version.is_empty())
+        .ok_or_else(|| format!("Invalid modloader id {id}").into())
+}
+
+async fn resolve_modloader(
+    minecraft: &ManifestMinecraft,
+) -> Result<InstanceModLoader, Box<dyn std::error::Error>> {
+    let modloader: Option<&ManifestModLoader> = minecraft
+        .mod_loaders
+        .iter()
+        .find(|modloader| modloader.primary)
+        .or(minecraft.mod_loaders.first());
+    let modloader: &ManifestModLoader = match modloader {
+        Some(modloader) => modloader,
+        None => return Ok(InstanceModLoader::Vanilla),
+    };
+
+    let (loader, version) = split_modloader_id(&modloader.id)?;
+    Ok(match loader {
+        "forge" => {
+            InstanceModLoader::Forge(forge::maven_version(&minecraft.version, version).await?)
+        }
+        "neoforge" => InstanceModLoader::NeoForge(version.to_string()),
+        "fabric" => InstanceModLoader::Fabric(version.to_string()),
+        "quilt" => InstanceModLoader::Quilt(version.to_string()),
+        _ => return Err(format!("Unsupported modloader {}", modloader.id).into()),
+    })
+}
+
+/// The hash to verify a download with, SHA-1 when CurseForge lists one and MD5 otherwise.
+fn file_checksum(file: &CurseForgeFile) -> Option<(String, ChecksumType)> {
+    let hash = |algo: u8| -> Option<String> {
+        file.hashes
+            .iter()
+            .find(|hash| hash.algo == algo && !hash.value.is_empty())
+            .map(|hash| hash.value.to_string())
+    };
+    match hash(SHA1_ALGORITHM) {
+        Some(sha1) => Some((sha1, ChecksumType::SHA1)),
+        None => hash(MD5_ALGORITHM).map(|md5| (md5, ChecksumType::MD5)),
+    }
+}
+
+fn target_folder(class_id: Option<u64>) -> &'static str {
+    match class_id {
+        Some(class_id) if class_id == RESOURCE_PACK_CLASS => "resourcepacks",
+        Some(class_id) if class_id == SHADER_PACK_CLASS => "shaderpacks",
+        _ => "mods",
+    }
+}
+
+/// Downloads every required file of the manifest and returns the ones that have to be supplied
+/// by hand.
+async fn download_files(
+    manifest: &Manifest,
+    instance_name: &str,
+    app: &AppHandle,
+) -> Result<Vec<CurseForgeMissingFile>, Box<dyn std::error::Error>> {
+    let file_ids: Vec<u64> = manifest
+        .files
+        .iter()
+        .filter(|file| file.required)
+        .map(|file| file.file_id)
+        .collect();
+    let mod_ids: Vec<u64> = manifest
+        .files
+        .iter()
+        .filter(|file| file.required)
+        .map(|file| file.project_id)
+        .collect();
+    if file_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let instance_path: PathBuf = get_data_root().join(format!("instances/{instance_name}"));
+    emit_progress(app, instance_name, "Resolving the modpack files");
+    let files: Vec<CurseForgeFile> =
+        post("/v1/mods/files", &json!({ "fileIds": file_ids })).await?;
+    let mods: HashMap<u64, CurseForgeMod> =
+        post::<Vec<CurseForgeMod>>("/v1/mods", &json!({ "modIds": mod_ids }))
+            .await?
+            .into_iter()
+            .map(|project| (project.id, project))
+            .collect();
+
+    let mut missing: Vec<CurseForgeMissingFile> = vec![];
+    let mut downloads: Vec<(String, String, ChecksumType, String)> = vec![];
+    for file in files.iter() {
+        let project: Option<&CurseForgeMod> = mods.get(&file.mod_id);
+        let path: String = format!(
+            "{}/{}",
+            target_folder(project.and_then(|project| project.class_id)),
+            file.file_name
+        );
+        // file names come from the api, they must stay inside the instance
+        safe_join(&instance_path, &path)?;
+
+        // without a hash the download can't be trusted, the user adds it like an opted out file
+        match (&file.download_url, file_checksum(file)) {
+            (Some(url), Some((hash, checksum_type))) => {
+                downloads.push((url.to_string(), hash, checksum_type, path))
+            }
+            _ => missing.push(CurseForgeMissingFile {
+                project_id: file.mod_id,
+                file_id: file.id,
+                project_name: project
+                    .map(|project| project.name.to_string())
+                    .unwrap_or_default(),
+                file_name: file.file_name.to_string(),
+                website_url: project
+                    .and_then(|project| project.links.website_url.as_ref())
+                    .map(|url| format!("{url}/files/{}", file.id))
+                    .unwrap_or_default(),
+                path,
+            }),
+        }
+    }
+
+    emit_progress(
+        app,
+        instance_name,
+        &format!("Downloading modpack files ({})", downloads.len()),
+    );
+
+    let failed: Vec<String> = stream::iter(downloads.into_iter().map(
+        |(url, hash, checksum_type, path): (String, String, ChecksumType, String)| {
+            let handle_copy: AppHandle = app.clone();
+            let instance_name_copy: String = instance_name.to_string();
+            async move {
+                match download_as_vec(
+                    &url,
+                    &hash,
+                    &checksum_type,
+                    &format!("instances/{instance_name_copy}/{path}"),
+                    false,
+                    false,
+                    Some((&handle_copy, &instance_name_copy)),
+                )
+                .await
+                {
+                    Ok(_) => None,
+                    Err(err) => {
+                        write_line(&format!("Failed to download {url}: {err}"));
+                        Some(path)
+                    }
+                }
+            }
+        },
+    ))
+    .buffer_unordered(50)
+    .filter_map(|failed: Option<String>| async move { failed })
+    .collect::<Vec<String>>()
+    .await;
+
+    if !failed.is_empty() {
+        return Err(format!("{} could not be downloaded", failed.join(", ")).into());
+    }
+
+    Ok(missing)
+}
+
+/// Creates the instance `instance_name` out of a CurseForge export zip. Files CurseForge doesn't
+/// distribute are listed in the report so the user can add them.
+pub async fn import(
+    path: &str,
+    instance_name: &str,
+    app: &AppHandle,
+) -> Result<CurseForgeImportReport, Box<dyn std::error::Error>> {
+    let pack: Vec<u8> = fs::read(path)?;
+    let instance_path: PathBuf = new_instance_path(instance_name)?;
+    let result: Result<CurseForgeImportReport, String> =
+        import_pack(&pack, instance_name, &instance_path, app)
+            .await
+            .map_err(|err| err.to_string());
+    finish(result, instance_name, &instance_path, app)
+}
+
+async fn import_pack(
+    pack: &[u8],
+    instance_name: &str,
+    instance_path: &Path,
+    app: &AppHandle,
+) -> Result<CurseForgeImportReport, Box<dyn std::error::Error>> {
+    emit_progress(app, instance_name, "Reading the modpack");
+    let manifest: Manifest = read_manifest(pack)?;
+    let modloader: InstanceModLoader = resolve_modloader(&manifest.minecraft).await?;
+
+    let missing: Vec<CurseForgeMissingFile> = download_files(&manifest, instance_name, app).await?;
+    extract_folder(pack, &manifest.overrides, instance_path)?;
+
+    let instance: InstanceInfo =
+        InstanceInfo::new(instance_name, &manifest.minecraft.version, &modloader);
+    install_instance(&instance, app).await?;
+
+    if !missing.is_empty() {
+        emit_progress(
+            app,
+            instance_name,
+            &format!(
+                "{} of {} needs files added by hand",
+                missing.len(),
+                manifest.name
+            ),
+        );
+    }
+    Ok(CurseForgeImportReport { instance, missing })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_manifest_loader_and_files() {
+        let manifest: Manifest = serde_json::from_value(json!({
+            "minecraft": {
+                "version": "1.20.1",
+                "modLoaders": [{ "id": "fabric-0.14.21", "primary": true }]
+            },
+            "manifestType": "minecraftModpack",
+            "manifestVersion": 1,
+            "name": "Pack",
+            "files": [
+                { "projectID": 238222, "fileID": 4593548, "required": true },
+                { "projectID": 306612, "fileID": 4586220 }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(manifest.overrides, "overrides");
+        assert!(manifest.files.iter().all(|file| file.required));
+        assert_eq!(
+            split_modloader_id(&manifest.minecraft.mod_loaders[0].id).unwrap(),
+            ("fabric", "0.14.21")
+        );
+        assert!(split_modloader_id("forge-").is_err());
+        assert_eq!(target_folder(Some(SHADER_PACK_CLASS)), "shaderpacks");
+        assert_eq!(target_folder(None), "mods");
+    }
+
+    #[test]
+    fn verifies_with_md5_when_there_is_no_sha1() {
+        let file = |hashes: Value| -> CurseForgeFile {
+            serde_json::from_value(json!({
+                "id": 4593548,
+                "modId": 238222,
+                "fileName": "jei.jar",
+                "hashes": hashes
+            }))
+            .unwrap()
+        };
+
+        let both: CurseForgeFile = file(json!([
+            { "value": "d41d8cd98f00b204e9800998ecf8427e", "algo": 2 },
+            { "value": "da39a3ee5e6b4b0d3255bfef95601890afd80709", "algo": 1 }
+        ]));
+        assert!(matches!(
+            file_checksum(&both),
+            Some((hash, ChecksumType::SHA1)) if hash == "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+        ));
+
+        let md5: CurseForgeFile = file(json!([
+            { "value": "d41d8cd98f00b204e9800998ecf8427e", "algo": 2 }
+        ]));
+        assert!(matches!(
+            file_checksum(&md5),
+            Some((hash, ChecksumType::MD5)) if hash == "d41d8cd98f00b204e9800998ecf8427e"
+        ));
+
+        assert!(file_checksum(&file(json!([]))).is_none());
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    fs::{self, File},
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+};
+
+use tauri::AppHandle;
+use zip::{read::ZipFile, ZipArchive};
+
+use crate::{
+    common::{
+        minecraft::instance::install_instance,
+        utils::{
+            directory::{get_data_root, safe_join, walk_files},
+            events::{emit_progress, emit_status},
+            log::write_line,
+        },
+    },
+    data::models::{ExternalImportMode, ExternalInstance, ExternalLauncher, InstanceInfo},
+};
+
+use super::{multimc, vanilla};
+
+/// The folder of a new instance, failing when the name is empty, isn't a single folder name or
+/// is already taken.
+pub fn new_instance_path(instance_name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if instance_name.trim().is_empty() {
+        return Err("The instance needs a name".into());
+    }
+    if instance_name.contains(['/', '\\']) || instance_name.contains("..") {
+        return Err(format!("{instance_name} is not a valid instance name").into());
+    }
+    let instance_path: PathBuf = safe_join(&get_data_root().join("instances"), instance_name)?;
+    if instance_path.exists() {
+        return Err(format!("An instance named {instance_name} already exists").into());
+    }
+    Ok(instance_path)
+}
+
+/// Extracts every entry under `folder/` of a pack into `instance_path`, the whole pack when
+/// `folder` is empty.
+pub fn extract_folder(
+    pack: &[u8],
+    folder: &str,
+    instance_path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let folder: &str = folder.trim_matches('/');
+    let prefix: String = if folder.is_empty() {
+        String::new()
+    } else {
+        format!("{folder}/")
+    };
+    let mut archive: ZipArchive<Cursor<&[u8]>> = ZipArchive::new(Cursor::new(pack))?;
+
+    for i in 0..archive.len() {
+        let mut file: ZipFile = archive.by_index(i)?;
+        let relative: String = match file.name().strip_prefix(&prefix) {
+            Some(relative) if !relative.is_empty() => relative.to_string(),
+            _ => continue,
+        };
+
+        // entry names come from the pack, never trust them
+        let outpath: PathBuf = safe_join(instance_path, &relative)?;
+        if file.is_dir() {
+            fs::create_dir_all(&outpath)?;
+            continue;
+        }
+        if let Some(parent) = outpath.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut outfile: File = File::create(&outpath)?;
+        io::copy(&mut file, &mut outfile)?;
+    }
+
+    Ok(())
+}
+
+/// Reports how an import went. A failed import has its half created instance removed, so it
+/// never shows up in the instance list.
+pub fn finish<T>(
+    result: Result<T, String>,
+    instance_name: &str,
+    instance_path: &Path,
+    app: &AppHandle,
+) -> Result<T, Box<dyn std::error::Error>> {
+    match result {
+        Ok(value) => {
+            emit_status(app, instance_name, "", "Success");
+            Ok(value)
+        }
+        Err(err) => {
+            write_line(&format!("Failed to import {instance_name}: {err}"));
+            let _ = fs::remove_dir_all(instance_path);
+            emit_status(app, instance_name, &err, "Error");
+            Err(err.into())
+        }
+    }
+}
+
+/// The instances of another launcher found at `path`.
+pub async fn list_external_instances(
+    launcher: ExternalLauncher,
+    path: &str,
+) -> Result<Vec<ExternalInstance>, Box<dyn std::error::Error>> {
+    match launcher {
+        ExternalLauncher::MultiMc => multimc::list_instances(path).await,
+        ExternalLauncher::Vanilla => vanilla::list_profiles(path).await,
+    }
+}
+
+/// Creates the instance `instance_name` out of the instance `id` of another launcher.
+pub async fn import_external(
+    launcher: ExternalLauncher,
+    path: &str,
+    id: &str,
+    instance_name: &str,
+    mode: ExternalImportMode,
+    app: &AppHandle,
+) -> Result<InstanceInfo, Box<dyn std::error::Error>> {
+    let external: ExternalInstance = list_external_instances(launcher, path)
+        .await?
+        .into_iter()
+        .find(|external| external.id == id)
+        .ok_or(format!("{id} was not found in {path}"))?;
+    let instance_path: PathBuf = new_instance_path(instance_name)?;
+
+    let result: Result<InstanceInfo, String> = import_instance(
+        launcher,
+        &external,
+        instance_name,
+        &instance_path,
+        mode,
+        app,
+    )
+    .await
+    .map_err(|err| err.to_string());
+    finish(result, instance_name, &instance_path, app)
+}
+
+async fn import_instance(
+    launcher: ExternalLauncher,
+    external: &ExternalInstance,
+    instance_name: &str,
+    instance_path: &Path,
+    mode: ExternalImportMode,
+    app: &AppHandle,
+) -> Result<InstanceInfo, Box<dyn std::error::Error>> {
+    let mut instance: InstanceInfo =
+        InstanceInfo::new(instance_name, &external.version, &external.modloader);
+    instance.addons = external.addons.clone();
+    instance.jvm_args = external.jvm_args.clone();
+
+    fs::create_dir_all(instance_path)?;
+    match mode {
+        ExternalImportMode::Copy => {
+            emit_progress(app, instance_name, "Copying the game folder");
+            let skip: &[&str] = match launcher {
+                ExternalLauncher::MultiMc => &[],
+                ExternalLauncher::Vanilla => &vanilla::LAUNCHER_FILES,
+            };
+            copy_game_dir(Path::new(&external.game_dir), instance_path, skip)?;
+        }
+        ExternalImportMode::Link => instance.game_dir = Some(external.game_dir.to_string()),
+    }
+
+    install_instance(&instance, app).await?;
+    Ok(instance)
+}
+
+/// Copies every file of `game_dir` into `instance_path`, but the top level entries in `skip`.
+fn copy_game_dir(
+    game_dir: &Path,
+    instance_path: &Path,
+    skip: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
+    for file in walk_files(game_dir) {
+        let relative: &Path = file.strip_prefix(game_dir)?;
+        let top_level: &str = relative
+            .components()
+            .next()
+            .and_then(|component| component.as_os_str().to_str())
+            .unwrap_or_default();
+        if skip.contains(&top_level) {
+            continue;
+        }
+
+        let target: PathBuf = safe_join(instance_path, relative.to_str().unwrap_or_default())?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&file, &target)?;
+    }
+    Ok(())
+}
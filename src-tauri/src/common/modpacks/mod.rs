@@ -1 +1,7 @@
-pub mod modrinth;
\ No newline at end of file
+pub mod archive;
+pub mod curseforge;
+pub mod import;
+pub mod modrinth;
+pub mod mrpack;
+pub mod multimc;
+pub mod vanilla;
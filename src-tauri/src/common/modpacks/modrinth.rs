@@ -1,20 +1,173 @@
-use reqwest::{header::USER_AGENT, Client};
-use serde_json::Value;
+use std::collections::HashMap;
 
-use crate::data::constants::{MODRINTH_BASE, USER_AGENT as PROJECT_USER_AGENT};
+use reqwest::{header::USER_AGENT, Client, Method, RequestBuilder, Response, Url};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::common::utils::{mirror::candidate_urls, network::ensure_online};
+use crate::data::{
+    constants::{MODRINTH_BASE, USER_AGENT as PROJECT_USER_AGENT},
+    models::{ModrinthSearchQuery, ModrinthSearchResult, ModrinthVersion},
+};
+
+static MAX_LIMIT: u32 = 100;
+static DEFAULT_LIMIT: u32 = 20;
+
+/// Sends a GET to the Modrinth api, `path` being relative to the v2 base.
+pub async fn get<T: DeserializeOwned>(
+    path: &str,
+    params: &[(&str, String)],
+) -> Result<T, Box<dyn std::error::Error>> {
+    request(Method::GET, path, params, None).await
+}
+
+pub async fn post<T: DeserializeOwned>(
+    path: &str,
+    body: &Value,
+) -> Result<T, Box<dyn std::error::Error>> {
+    request(Method::POST, path, &[], Some(body)).await
+}
+
+async fn request<T: DeserializeOwned>(
+    method: Method,
+    path: &str,
+    params: &[(&str, String)],
+    body: Option<&Value>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    ensure_online()?;
 
-pub async fn fetch_modpacks() -> Result<Value, Box<dyn std::error::Error>> {
     let client: Client = Client::new();
+    let url: Url = Url::parse_with_params(&format!("{MODRINTH_BASE}{path}"), params)?;
+
+    let mut last_error: Option<reqwest::Error> = None;
+    for candidate in candidate_urls(url.as_str()).await {
+        let mut builder: RequestBuilder = client
+            .request(method.clone(), &candidate)
+            .header(USER_AGENT, PROJECT_USER_AGENT);
+        if let Some(body) = body {
+            builder = builder.json(body);
+        }
+        let response: Result<Response, reqwest::Error> = builder
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        // a mirror answering with something unexpected falls through to the next candidate
+        match response {
+            Ok(response) => match response.json().await {
+                Ok(data) => return Ok(data),
+                Err(err) => last_error = Some(err),
+            },
+            Err(err) => last_error = Some(err),
+        }
+    }
+
+    match last_error {
+        Some(err) => Err(Box::new(err)),
+        None => Err(format!("There is no url to reach {path} through").into()),
+    }
+}
+
+// every group is or-ed internally and and-ed with the others
+fn facets(query: &ModrinthSearchQuery) -> String {
+    let mut groups: Vec<Vec<String>> = vec![
+        query
+            .project_types
+            .iter()
+            .map(|project_type| format!("project_type:{}", project_type.as_str()))
+            .collect(),
+        query
+            .game_versions
+            .iter()
+            .map(|version| format!("versions:{version}"))
+            .collect(),
+        // loaders are categories to the search index
+        query
+            .loaders
+            .iter()
+            .map(|loader| format!("categories:{}", loader.to_lowercase()))
+            .collect(),
+    ];
+    groups.extend(
+        query
+            .categories
+            .iter()
+            .map(|category| vec![format!("categories:{category}")]),
+    );
+    groups.retain(|group| !group.is_empty());
+    serde_json::to_string(&groups).unwrap()
+}
+
+pub async fn search(
+    query: &ModrinthSearchQuery,
+) -> Result<ModrinthSearchResult, Box<dyn std::error::Error>> {
+    let limit: u32 = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let mut params: Vec<(&str, String)> = vec![
+        ("facets", facets(query)),
+        ("index", query.index.as_str().to_string()),
+        ("offset", query.offset.to_string()),
+        ("limit", limit.to_string()),
+    ];
+    if !query.query.trim().is_empty() {
+        params.push(("query", query.query.trim().to_string()));
+    }
+
+    get("/search", &params).await
+}
+
+pub async fn get_version(version_id: &str) -> Result<ModrinthVersion, Box<dyn std::error::Error>> {
+    get(&format!("/version/{version_id}"), &[]).await
+}
+
+/// The versions of a project, newest first, optionally narrowed to some loaders and game versions.
+pub async fn get_project_versions(
+    project_id: &str,
+    loaders: &[String],
+    game_versions: &[String],
+) -> Result<Vec<ModrinthVersion>, Box<dyn std::error::Error>> {
+    let mut params: Vec<(&str, String)> = vec![];
+    if !loaders.is_empty() {
+        params.push(("loaders", serde_json::to_string(loaders)?));
+    }
+    if !game_versions.is_empty() {
+        params.push(("game_versions", serde_json::to_string(game_versions)?));
+    }
+    get(&format!("/project/{project_id}/version"), &params).await
+}
+
+/// The versions the files with these SHA-512 hashes belong to, keyed by hash. Unknown files are
+/// left out.
+pub async fn get_versions_by_hash(
+    hashes: &[String],
+) -> Result<HashMap<String, ModrinthVersion>, Box<dyn std::error::Error>> {
+    if hashes.is_empty() {
+        return Ok(HashMap::new());
+    }
+    post(
+        "/version_files",
+        &json!({ "hashes": hashes, "algorithm": "sha512" }),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::models::ModrinthProjectType;
+
+    #[test]
+    fn facets_or_values_and_groups() {
+        let query: ModrinthSearchQuery = ModrinthSearchQuery {
+            project_types: vec![ModrinthProjectType::Mod, ModrinthProjectType::Shader],
+            game_versions: vec![String::from("1.20.1")],
+            loaders: vec![String::from("Fabric")],
+            categories: vec![String::from("optimization"), String::from("utility")],
+            ..Default::default()
+        };
 
-    let modpacks: Value = client
-        .get(format!(
-            "{MODRINTH_BASE}/search?facets=[[\"project_type:modpack\"]]&limit=50"
-        ))
-        .header(USER_AGENT, PROJECT_USER_AGENT)
-        .send()
-        .await?
-        .json()
-        .await?;
-
-    Ok(modpacks)
+        assert_eq!(
+            facets(&query),
+            r#"[["project_type:mod","project_type:shader"],["versions:1.20.1"],["categories:fabric"],["categories:optimization"],["categories:utility"]]"#
+        );
+        assert_eq!(facets(&ModrinthSearchQuery::default()), "[]");
+    }
 }
@@ -0,0 +1,479 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use futures::{stream, StreamExt};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use zip::{read::ZipFile, write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    common::{
+        minecraft::instance::{game_path, install_instance},
+        modloader::forge,
+        utils::{
+            directory::{get_data_root, is_excluded, relative_files, safe_join},
+            events::emit_progress,
+            file::{checksum_of, download_as_vec, read_as_value, ChecksumType},
+            log::write_line,
+        },
+    },
+    data::models::{
+        InstanceInfo, InstanceModLoader, ModrinthHashes, ModrinthVersion, ModrinthVersionFile,
+        MrpackExportOptions,
+    },
+};
+
+use super::{
+    import::{extract_folder, finish, new_instance_path},
+    modrinth,
+};
+
+static INDEX_FILE: &str = "modrinth.index.json";
+
+// launcher state inside the instance folder, never part of a pack
+static LAUNCHER_FILES: [&str; 2] = ["atlas_instance.json", "resources"];
+
+// the hosts the mrpack format allows files to be downloaded from
+static ALLOWED_HOSTS: [&str; 4] = [
+    "cdn.modrinth.com",
+    "github.com",
+    "raw.githubusercontent.com",
+    "gitlab.com",
+];
+
+/// The `modrinth.index.json` at the root of a `.mrpack`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MrpackIndex {
+    pub format_version: u32,
+    pub game: String,
+    pub version_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub files: Vec<MrpackFile>,
+    /// `minecraft` plus at most one of `forge`, `neoforge`, `fabric-loader` and `quilt-loader`.
+    pub dependencies: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MrpackFile {
+    pub path: String,
+    pub hashes: ModrinthHashes,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<MrpackEnv>,
+    pub downloads: Vec<String>,
+    #[serde(default)]
+    pub file_size: u64,
+}
+
+/// Each side is `required`, `optional` or `unsupported`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MrpackEnv {
+    pub client: String,
+    pub server: String,
+}
+
+impl MrpackFile {
+    fn client_supported(&self) -> bool {
+        !matches!(&self.env, Some(env) if env.client == "unsupported")
+    }
+}
+
+fn read_index(pack: &[u8]) -> Result<MrpackIndex, Box<dyn std::error::Error>> {
+    let mut archive: ZipArchive<Cursor<&[u8]>> = ZipArchive::new(Cursor::new(pack))?;
+    let mut index_file: ZipFile = archive
+        .by_name(INDEX_FILE)
+        .map_err(|_| format!("The modpack has no {INDEX_FILE}"))?;
+    let mut contents: Vec<u8> = vec![];
+    index_file.read_to_end(&mut contents)?;
+
+    let index: MrpackIndex = serde_json::from_slice(&contents)?;
+    if index.format_version != 1 || index.game != "minecraft" {
+        return Err(format!(
+            "Unsupported modpack format {} for {}",
+            index.format_version, index.game
+        )
+        .into());
+    }
+    Ok(index)
+}
+
+/// The game version and loader an index depends on.
+async fn resolve_dependencies(
+    dependencies: &HashMap<String, String>,
+) -> Result<(String, InstanceModLoader), Box<dyn std::error::Error>> {
+    let game_version: String = dependencies
+        .get("minecraft")
+        .ok_or("The modpack doesn't declare a Minecraft version")?
+        .to_string();
+
+    let mut modloader: InstanceModLoader = InstanceModLoader::Vanilla;
+    for (dependency, version) in dependencies {
+        let loader: InstanceModLoader = match dependency.as_str() {
+            "minecraft" => continue,
+            "forge" => {
+                InstanceModLoader::Forge(forge::maven_version(&game_version, version).await?)
+            }
+            "neoforge" => InstanceModLoader::NeoForge(version.to_string()),
+            "fabric-loader" => InstanceModLoader::Fabric(version.to_string()),
+            "quilt-loader" => InstanceModLoader::Quilt(version.to_string()),
+            _ => return Err(format!("Unsupported modpack dependency {dependency}").into()),
+        };
+        if modloader != InstanceModLoader::Vanilla {
+            return Err("The modpack depends on more than one loader".into());
+        }
+        modloader = loader;
+    }
+
+    Ok((game_version, modloader))
+}
+
+fn allowed_download(url: &str) -> bool {
+    Url::parse(url)
+        .ok()
+        .filter(|url| url.scheme() == "https")
+        .and_then(|url| url.host_str().map(|host| ALLOWED_HOSTS.contains(&host)))
+        .unwrap_or(false)
+}
+
+async fn download_files(
+    index: &MrpackIndex,
+    instance_name: &str,
+    app: &AppHandle,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let instance_path: PathBuf = get_data_root().join(format!("instances/{instance_name}"));
+    let files: Vec<MrpackFile> = index
+        .files
+        .iter()
+        .filter(|file| file.client_supported())
+        .cloned()
+        .collect();
+
+    for file in files.iter() {
+        // paths come from the pack, they must stay inside the instance
+        safe_join(&instance_path, &file.path)?;
+        if file.hashes.sha512.is_empty() {
+            return Err(format!("{} has no SHA-512 hash", file.path).into());
+        }
+    }
+
+    emit_progress(
+        app,
+        instance_name,
+        &format!("Downloading modpack files ({})", files.len()),
+    );
+
+    let failed: Vec<String> = stream::iter(files.into_iter().map(|file: MrpackFile| {
+        let handle_copy: AppHandle = app.clone();
+        let instance_name_copy: String = instance_name.to_string();
+        async move {
+            for url in file.downloads.iter() {
+                if !allowed_download(url) {
+                    write_line(&format!(
+                        "Skipping {url}, the host is not allowed in modpacks"
+                    ));
+                    continue;
+                }
+                match download_as_vec(
+                    url,
+                    &file.hashes.sha512,
+                    &ChecksumType::SHA512,
+                    &format!("instances/{instance_name_copy}/{}", file.path),
+                    false,
+                    false,
+                    Some((&handle_copy, &instance_name_copy)),
+                )
+                .await
+                {
+                    Ok(_) => return None,
+                    Err(err) => write_line(&format!("Failed to download {url}: {err}")),
+                }
+            }
+            Some(file.path)
+        }
+    }))
+    .buffer_unordered(50)
+    .filter_map(|failed: Option<String>| async move { failed })
+    .collect::<Vec<String>>()
+    .await;
+
+    if !failed.is_empty() {
+        return Err(format!("{} could not be downloaded", failed.join(", ")).into());
+    }
+
+    Ok(())
+}
+
+/// Creates the instance `instance_name` out of a `.mrpack`. A failed install leaves nothing behind.
+pub async fn install(
+    pack: Vec<u8>,
+    instance_name: &str,
+    app: &AppHandle,
+) -> Result<InstanceInfo, Box<dyn std::error::Error>> {
+    let instance_path: PathBuf = new_instance_path(instance_name)?;
+    let result: Result<InstanceInfo, String> = install_pack(&pack, instance_name, app)
+        .await
+        .map_err(|err| err.to_string());
+    finish(result, instance_name, &instance_path, app)
+}
+
+async fn install_pack(
+    pack: &[u8],
+    instance_name: &str,
+    app: &AppHandle,
+) -> Result<InstanceInfo, Box<dyn std::error::Error>> {
+    emit_progress(app, instance_name, "Reading the modpack");
+    let index: MrpackIndex = read_index(pack)?;
+    let (game_version, modloader) = resolve_dependencies(&index.dependencies).await?;
+
+    download_files(&index, instance_name, app).await?;
+    // the client specific files win over the shared ones
+    let instance_path: PathBuf = get_data_root().join(format!("instances/{instance_name}"));
+    extract_folder(pack, "overrides", &instance_path)?;
+    extract_folder(pack, "client-overrides", &instance_path)?;
+
+    let instance: InstanceInfo = InstanceInfo::new(instance_name, &game_version, &modloader);
+    install_instance(&instance, app).await?;
+    Ok(instance)
+}
+
+pub async fn install_from_file(
+    path: &str,
+    instance_name: &str,
+    app: &AppHandle,
+) -> Result<InstanceInfo, Box<dyn std::error::Error>> {
+    let pack: Vec<u8> = fs::read(path)?;
+    install(pack, instance_name, app).await
+}
+
+/// Installs a modpack version from Modrinth, the newest one when no version is given.
+pub async fn install_from_modrinth(
+    project_id: &str,
+    version_id: Option<&str>,
+    instance_name: &str,
+    app: &AppHandle,
+) -> Result<InstanceInfo, Box<dyn std::error::Error>> {
+    let version: ModrinthVersion = match version_id {
+        Some(version_id) => modrinth::get_version(version_id).await?,
+        None => modrinth::get_project_versions(project_id, &[], &[])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(format!("{project_id} has no versions"))?,
+    };
+    let file: &ModrinthVersionFile = version
+        .primary_file()
+        .ok_or(format!("{} has no files", version.name))?;
+
+    emit_progress(
+        app,
+        instance_name,
+        &format!("Downloading {}", file.filename),
+    );
+    let pack: Vec<u8> = download_as_vec(
+        &file.url,
+        &file.hashes.sha512,
+        &ChecksumType::SHA512,
+        &format!("launcher/modpacks/{}/{}", version.project_id, file.filename),
+        false,
+        false,
+        None,
+    )
+    .await?;
+    install(pack, instance_name, app).await
+}
+
+/// The `dependencies` of an index for the instance.
+fn export_dependencies(
+    instance: &InstanceInfo,
+) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    if !instance.addons.is_empty() {
+        return Err("Addon loaders can't be exported to a .mrpack".into());
+    }
+
+    let game_version: &str = &instance.version;
+    let mut dependencies: HashMap<String, String> = HashMap::new();
+    dependencies.insert(String::from("minecraft"), game_version.to_string());
+
+    let loader: Option<(&str, String)> = match &instance.modloader {
+        InstanceModLoader::Vanilla => None,
+        InstanceModLoader::Forge(version) => Some((
+            "forge",
+            forge::build_number(version, game_version).to_string(),
+        )),
+        InstanceModLoader::NeoForge(version) => Some(("neoforge", version.to_string())),
+        InstanceModLoader::Fabric(version) => Some(("fabric-loader", version.to_string())),
+        InstanceModLoader::Quilt(version) => Some(("quilt-loader", version.to_string())),
+        modloader => return Err(format!("{modloader} can't be exported to a .mrpack").into()),
+    };
+    if let Some((key, version)) = loader {
+        dependencies.insert(key.to_string(), version);
+    }
+    Ok(dependencies)
+}
+
+/// Every file of an instance that can go into a pack, relative to the instance folder.
+pub async fn list_instance_files(
+    instance_name: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let instance: InstanceInfo =
+        read_as_value(&format!("instances/{instance_name}/atlas_instance.json")).await?;
+    let game_path: PathBuf = game_path(&instance);
+
+    let mut launcher_files: Vec<String> =
+        LAUNCHER_FILES.iter().map(|file| file.to_string()).collect();
+    for image in [&instance.background, &instance.icon] {
+        if !image.starts_with("default") {
+            launcher_files.push(image.to_string());
+        }
+    }
+
+    Ok(relative_files(&game_path)
+        .into_iter()
+        .filter(|file| !is_excluded(file, &launcher_files))
+        .collect())
+}
+
+/// Packs an instance into a `.mrpack` at `output`. Mods Modrinth knows by hash are referenced by
+/// url, every other file is stored under `overrides/`.
+pub async fn export(
+    instance_name: &str,
+    options: &MrpackExportOptions,
+    output: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let instance: InstanceInfo =
+        read_as_value(&format!("instances/{instance_name}/atlas_instance.json")).await?;
+    let dependencies: HashMap<String, String> = export_dependencies(&instance)?;
+    let instance_path: PathBuf = game_path(&instance);
+
+    let paths: Vec<String> = list_instance_files(instance_name)
+        .await?
+        .into_iter()
+        .filter(|path| options.files.is_empty() || options.files.contains(path))
+        .filter(|path| !is_excluded(path, &options.exclude))
+        .collect();
+
+    let mut mod_hashes: HashMap<String, String> = HashMap::new();
+    for path in paths.iter() {
+        if path.starts_with("mods/") && path.ends_with(".jar") {
+            let data: Vec<u8> = fs::read(instance_path.join(path))?;
+            mod_hashes.insert(checksum_of(&ChecksumType::SHA512, &data)?, path.to_string());
+        }
+    }
+    let hashes: Vec<String> = mod_hashes.keys().cloned().collect();
+    let versions: HashMap<String, ModrinthVersion> = match modrinth::get_versions_by_hash(&hashes)
+        .await
+    {
+        Ok(versions) => versions,
+        Err(err) => {
+            write_line(&format!("Failed to look up the mods of {instance_name} on Modrinth, packing them as overrides: {err}"));
+            HashMap::new()
+        }
+    };
+
+    let mut files: Vec<MrpackFile> = vec![];
+    let mut overrides: Vec<&str> = vec![];
+    for path in paths.iter() {
+        let data: Vec<u8> = fs::read(instance_path.join(path))?;
+        let sha512: String = checksum_of(&ChecksumType::SHA512, &data)?;
+        let version_file: Option<&ModrinthVersionFile> = versions
+            .get(&sha512)
+            .filter(|_| mod_hashes.get(&sha512) == Some(path))
+            .and_then(|version| {
+                version
+                    .files
+                    .iter()
+                    .find(|file| file.hashes.sha512 == sha512)
+            });
+
+        match version_file {
+            Some(version_file) => files.push(MrpackFile {
+                path: path.to_string(),
+                hashes: ModrinthHashes {
+                    sha1: checksum_of(&ChecksumType::SHA1, &data)?,
+                    sha512,
+                },
+                env: None,
+                downloads: vec![version_file.url.to_string()],
+                file_size: data.len() as u64,
+            }),
+            None => overrides.push(path),
+        }
+    }
+
+    let index: MrpackIndex = MrpackIndex {
+        format_version: 1,
+        game: String::from("minecraft"),
+        version_id: options.version_id.to_string(),
+        name: if options.name.trim().is_empty() {
+            instance.name.to_string()
+        } else {
+            options.name.to_string()
+        },
+        summary: options.summary.clone(),
+        files,
+        dependencies,
+    };
+
+    write_pack(&index, &overrides, &instance_path, Path::new(output))
+}
+
+fn write_pack(
+    index: &MrpackIndex,
+    overrides: &[&str],
+    instance_path: &Path,
+    output: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut pack: ZipWriter<File> = ZipWriter::new(File::create(output)?);
+    let options: FileOptions =
+        FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    pack.start_file(INDEX_FILE, options)?;
+    pack.write_all(&serde_json::to_vec_pretty(index)?)?;
+
+    for path in overrides {
+        pack.start_file(format!("overrides/{path}"), options)?;
+        io::copy(&mut File::open(instance_path.join(path))?, &mut pack)?;
+    }
+
+    pack.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forge_dependencies_carry_the_build_number() {
+        let modloader: InstanceModLoader = InstanceModLoader::Forge(String::from("1.20.1-47.1.0"));
+        let instance: InstanceInfo = InstanceInfo::new("pack", "1.20.1", &modloader);
+        let dependencies: HashMap<String, String> = export_dependencies(&instance).unwrap();
+        assert_eq!(dependencies["minecraft"], "1.20.1");
+        assert_eq!(dependencies["forge"], "47.1.0");
+
+        let modloader: InstanceModLoader =
+            InstanceModLoader::OptiFine(String::from("1.20.1_HD_U_I5"));
+        let instance: InstanceInfo = InstanceInfo::new("pack", "1.20.1", &modloader);
+        assert!(export_dependencies(&instance).is_err());
+    }
+
+    #[test]
+    fn only_https_downloads_from_allowed_hosts() {
+        assert!(allowed_download(
+            "https://cdn.modrinth.com/data/AANobbMI/versions/1.0/sodium.jar"
+        ));
+        assert!(!allowed_download("http://cdn.modrinth.com/data/sodium.jar"));
+        assert!(!allowed_download(
+            "https://cdn.modrinth.com.evil.net/sodium.jar"
+        ));
+        assert!(!allowed_download("not a url"));
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    common::modloader::forge,
+    data::models::{ExternalInstance, InstanceModLoader},
+};
+
+#[derive(Deserialize)]
+struct Pack {
+    components: Vec<Component>,
+}
+
+#[derive(Deserialize)]
+struct Component {
+    uid: String,
+    #[serde(default)]
+    version: String,
+}
+
+/// Reads the `key=value` lines of an `instance.cfg`, sections are ignored.
+fn read_config(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+fn jvm_args(config: &HashMap<String, String>) -> Vec<String> {
+    let enabled = |key: &str| config.get(key).map(|value| value == "true") == Some(true);
+
+    let mut jvm_args: Vec<String> = vec![];
+    if enabled("OverrideMemory") {
+        if let Some(min) = config.get("MinMemAlloc") {
+            jvm_args.push(format!("-Xms{min}M"));
+        }
+        if let Some(max) = config.get("MaxMemAlloc") {
+            jvm_args.push(format!("-Xmx{max}M"));
+        }
+    }
+    if enabled("OverrideJavaArgs") {
+        if let Some(args) = config.get("JvmArgs") {
+            jvm_args.extend(args.split_whitespace().map(|arg| arg.to_string()));
+        }
+    }
+    jvm_args
+}
+
+/// Maps the pack components to the game version, loader and addons.
+async fn read_components(
+    components: &[Component],
+) -> Result<(String, InstanceModLoader, Vec<InstanceModLoader>), Box<dyn std::error::Error>> {
+    let game_version: String = components
+        .iter()
+        .find(|component| component.uid == "net.minecraft")
+        .map(|component| component.version.to_string())
+        .ok_or("The instance has no Minecraft component")?;
+
+    let mut modloader: InstanceModLoader = InstanceModLoader::Vanilla;
+    let mut addons: Vec<InstanceModLoader> = vec![];
+    for component in components {
+        let version: String = component.version.to_string();
+        match component.uid.as_str() {
+            "net.minecraftforge" => {
+                modloader =
+                    InstanceModLoader::Forge(forge::maven_version(&game_version, &version).await?)
+            }
+            "net.neoforged" => modloader = InstanceModLoader::NeoForge(version),
+            "net.fabricmc.fabric-loader" => modloader = InstanceModLoader::Fabric(version),
+            "org.quiltmc.quilt-loader" => modloader = InstanceModLoader::Quilt(version),
+            "com.mumfrey.liteloader" => addons.push(InstanceModLoader::LiteLoader(version)),
+            // mappings, lwjgl and the like come with the version
+            _ => {}
+        }
+    }
+
+    Ok((game_version, modloader, addons))
+}
+
+async fn read_instance(path: &Path) -> Result<ExternalInstance, Box<dyn std::error::Error>> {
+    let config: HashMap<String, String> =
+        read_config(&fs::read_to_string(path.join("instance.cfg"))?);
+    let pack: Pack = serde_json::from_slice(&fs::read(path.join("mmc-pack.json"))?)?;
+    let (version, modloader, addons) = read_components(&pack.components).await?;
+
+    let id: String = path
+        .file_name()
+        .and_then(|id| id.to_str())
+        .unwrap_or_default()
+        .to_string();
+    // older instances keep the game in `minecraft`
+    let game_dir: PathBuf = [".minecraft", "minecraft"]
+        .iter()
+        .map(|folder| path.join(folder))
+        .find(|game_dir| game_dir.is_dir())
+        .unwrap_or(path.join(".minecraft"));
+
+    Ok(ExternalInstance {
+        name: config.get("name").cloned().unwrap_or(id.to_string()),
+        id,
+        version,
+        modloader,
+        addons,
+        game_dir: game_dir.to_str().unwrap_or_default().to_string(),
+        jvm_args: jvm_args(&config),
+    })
+}
+
+/// The instances in a MultiMC or Prism Launcher `instances` folder, `path` may also be a single
+/// instance folder. Instances that can't be read are skipped.
+pub async fn list_instances(
+    path: &str,
+) -> Result<Vec<ExternalInstance>, Box<dyn std::error::Error>> {
+    let path: &Path = Path::new(path);
+    if path.join("instance.cfg").is_file() {
+        return Ok(vec![read_instance(path).await?]);
+    }
+
+    let mut instances: Vec<ExternalInstance> = vec![];
+    for entry in fs::read_dir(path)?.flatten() {
+        if !entry.path().join("instance.cfg").is_file() {
+            continue;
+        }
+        if let Ok(instance) = read_instance(&entry.path()).await {
+            instances.push(instance);
+        }
+    }
+    instances.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(instances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_memory_and_java_overrides() {
+        let config: HashMap<String, String> = read_config(
+            "[General]\nname=Pack\nOverrideMemory=true\nMinMemAlloc=512\nMaxMemAlloc=4096\nOverrideJavaArgs=false\nJvmArgs=-XX:+UseZGC\n",
+        );
+        assert_eq!(config["name"], "Pack");
+        assert_eq!(jvm_args(&config), vec!["-Xms512M", "-Xmx4096M"]);
+    }
+}
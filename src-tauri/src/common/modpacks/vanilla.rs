@@ -0,0 +1,218 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    common::{modloader::forge, utils::file::read_as_value},
+    data::{
+        constants::NET_MINECRAFT_MOJANG_VERSION_MANIFEST,
+        models::{ExternalInstance, InstanceModLoader, ModLoaderType},
+    },
+};
+
+/// What the official launcher keeps next to the game files, never copied into an instance.
+pub static LAUNCHER_FILES: [&str; 9] = [
+    "assets",
+    "libraries",
+    "versions",
+    "runtime",
+    "webcache2",
+    "launcher_profiles.json",
+    "launcher_accounts.json",
+    "launcher_settings.json",
+    "launcher_log.txt",
+];
+
+#[derive(Deserialize)]
+struct LauncherProfiles {
+    profiles: HashMap<String, Profile>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Profile {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    r#type: String,
+    #[serde(default)]
+    last_version_id: String,
+    #[serde(default)]
+    game_dir: Option<String>,
+    #[serde(default)]
+    java_args: Option<String>,
+}
+
+/// The loader a version id of the official launcher stands for, as (game version, loader).
+/// Forge only carries its build number here.
+fn split_version_id(id: &str) -> (String, Option<(ModLoaderType, String)>) {
+    // fabric-loader-0.14.21-1.20.1, quilt-loader-0.19.1-1.20.1
+    for (prefix, loader) in [
+        ("fabric-loader-", ModLoaderType::Fabric),
+        ("quilt-loader-", ModLoaderType::Quilt),
+    ] {
+        if let Some((loader_version, game_version)) = id
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.split_once('-'))
+        {
+            return (
+                game_version.to_string(),
+                Some((loader, loader_version.to_string())),
+            );
+        }
+    }
+
+    // 1.20.1-forge-47.1.0, 1.12.2-forge1.12.2-14.23.5.2859
+    if let Some((game_version, build)) = id.split_once("-forge") {
+        let build: &str = build.trim_start_matches('-');
+        let build: &str = build
+            .strip_prefix(&format!("{game_version}-"))
+            .unwrap_or(build);
+        return (
+            game_version.to_string(),
+            Some((ModLoaderType::Forge, build.to_string())),
+        );
+    }
+
+    // 1.20.1-OptiFine_HD_U_I5
+    if let Some((game_version, edition)) = id.split_once("-OptiFine_") {
+        return (
+            game_version.to_string(),
+            Some((ModLoaderType::OptiFine, format!("{game_version}_{edition}"))),
+        );
+    }
+
+    (id.to_string(), None)
+}
+
+// the version json of a modded id knows the game version it inherits from
+fn inherited_version(root: &Path, id: &str) -> Option<String> {
+    let contents: Vec<u8> = fs::read(root.join(format!("versions/{id}/{id}.json"))).ok()?;
+    let manifest: Value = serde_json::from_slice(&contents).ok()?;
+    manifest["inheritsFrom"].as_str().map(|id| id.to_string())
+}
+
+async fn resolve_version(
+    root: &Path,
+    profile: &Profile,
+) -> Result<(String, InstanceModLoader), Box<dyn std::error::Error>> {
+    let latest: Option<&str> = match profile.r#type.as_str() {
+        "latest-release" => Some("release"),
+        "latest-snapshot" => Some("snapshot"),
+        _ => None,
+    };
+    if let Some(latest) = latest {
+        let manifest: Value = read_as_value(NET_MINECRAFT_MOJANG_VERSION_MANIFEST).await?;
+        let version: &str = manifest["latest"][latest]
+            .as_str()
+            .ok_or("The version manifest has no latest version")?;
+        return Ok((version.to_string(), InstanceModLoader::Vanilla));
+    }
+
+    let id: &str = &profile.last_version_id;
+    if let Some(version) = id.strip_prefix("neoforge-") {
+        let game_version: String =
+            inherited_version(root, id).ok_or(format!("The {id} version json is missing"))?;
+        return Ok((
+            game_version,
+            InstanceModLoader::NeoForge(version.to_string()),
+        ));
+    }
+
+    let (mut game_version, loader) = split_version_id(id);
+    if let Some(inherited) = inherited_version(root, id) {
+        game_version = inherited;
+    }
+    let modloader: InstanceModLoader = match loader {
+        None => InstanceModLoader::Vanilla,
+        Some((ModLoaderType::Forge, build)) => {
+            InstanceModLoader::Forge(forge::maven_version(&game_version, &build).await?)
+        }
+        Some((ModLoaderType::NeoForge, version)) => InstanceModLoader::NeoForge(version),
+        Some((ModLoaderType::Fabric, version)) => InstanceModLoader::Fabric(version),
+        Some((ModLoaderType::Quilt, version)) => InstanceModLoader::Quilt(version),
+        Some((ModLoaderType::OptiFine, version)) => InstanceModLoader::OptiFine(version),
+        Some((ModLoaderType::LiteLoader, version)) => InstanceModLoader::LiteLoader(version),
+    };
+    Ok((game_version, modloader))
+}
+
+/// The profiles of the official launcher whose `.minecraft` folder is `path`. Profiles whose
+/// version can't be mapped are skipped.
+pub async fn list_profiles(
+    path: &str,
+) -> Result<Vec<ExternalInstance>, Box<dyn std::error::Error>> {
+    let root: &Path = Path::new(path);
+    let launcher_profiles: LauncherProfiles =
+        serde_json::from_slice(&fs::read(root.join("launcher_profiles.json"))?)?;
+
+    let mut profiles: Vec<ExternalInstance> = vec![];
+    for (id, profile) in launcher_profiles.profiles.iter() {
+        let (version, modloader) = match resolve_version(root, profile).await.ok() {
+            Some(resolved) => resolved,
+            None => continue,
+        };
+        let name: String = if profile.name.is_empty() {
+            profile.last_version_id.to_string()
+        } else {
+            profile.name.to_string()
+        };
+
+        profiles.push(ExternalInstance {
+            id: id.to_string(),
+            name,
+            version,
+            modloader,
+            addons: vec![],
+            game_dir: profile.game_dir.clone().unwrap_or(path.to_string()),
+            jvm_args: profile
+                .java_args
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(|arg| arg.to_string())
+                .collect(),
+        });
+    }
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_modded_version_ids() {
+        assert_eq!(
+            split_version_id("fabric-loader-0.14.21-1.20.1"),
+            (
+                String::from("1.20.1"),
+                Some((ModLoaderType::Fabric, String::from("0.14.21")))
+            )
+        );
+        assert_eq!(
+            split_version_id("1.20.1-forge-47.1.0"),
+            (
+                String::from("1.20.1"),
+                Some((ModLoaderType::Forge, String::from("47.1.0")))
+            )
+        );
+        assert_eq!(
+            split_version_id("1.12.2-forge1.12.2-14.23.5.2859"),
+            (
+                String::from("1.12.2"),
+                Some((ModLoaderType::Forge, String::from("14.23.5.2859")))
+            )
+        );
+        assert_eq!(
+            split_version_id("1.20.1-OptiFine_HD_U_I5"),
+            (
+                String::from("1.20.1"),
+                Some((ModLoaderType::OptiFine, String::from("1.20.1_HD_U_I5")))
+            )
+        );
+        assert_eq!(split_version_id("1.20.1"), (String::from("1.20.1"), None));
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    collections::HashMap,
+    fs,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::{
+    header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, USER_AGENT},
+    Client, RequestBuilder, Response, StatusCode,
+};
+
+use crate::data::{
+    constants::{METADATA_CACHE_INDEX, USER_AGENT as PROJECT_USER_AGENT},
+    models::MetadataCacheEntry,
+};
+
+use super::{
+    directory::get_data_root,
+    file::{read_as_vec, write_value, write_vec},
+    log::write_line,
+    mirror::candidate_urls,
+    network::{ensure_online, is_offline},
+    settings::get_settings,
+};
+
+// manifests are fetched in parallel, the index is read-modify-written under this lock
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// Fetches a metadata file, reusing the copy at `path` while it is younger than the configured
+/// ttl and revalidating it with `If-None-Match`/`If-Modified-Since` afterwards.
+pub async fn fetch_metadata(
+    url: &str,
+    path: &str,
+    force: bool,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let entry: Option<MetadataCacheEntry> = read_index().remove(url);
+    let cached: Option<Vec<u8>> = read_as_vec(path).await.ok();
+
+    if let Some(cached) = &cached {
+        let ttl: u64 = get_settings().await.metadata_ttl_minutes * 60;
+        let fresh: bool = match &entry {
+            Some(entry) => now().saturating_sub(entry.fetched_at) < ttl,
+            None => false,
+        };
+        if is_offline() || (!force && fresh) {
+            return Ok(cached.to_owned());
+        }
+    }
+
+    ensure_online()?;
+
+    let mut response: Result<Response, String> = Err(String::new());
+    for candidate in candidate_urls(url).await {
+        let mut request: RequestBuilder = Client::new()
+            .get(&candidate)
+            .header(USER_AGENT, PROJECT_USER_AGENT);
+        if let (Some(entry), Some(_)) = (&entry, &cached) {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        response = request.send().await.map_err(|err| err.to_string());
+        match &response {
+            Ok(sent) if sent.status().is_success() || sent.status() == StatusCode::NOT_MODIFIED => {
+                break
+            }
+            Ok(sent) => write_line(&format!("{candidate} answered {}", sent.status())),
+            Err(err) => write_line(&format!("Could not reach {candidate}: {err}")),
+        }
+    }
+
+    // every candidate failing with an error status is as bad as none being reachable
+    let response: Result<Response, String> = response.and_then(|response| {
+        let status: StatusCode = response.status();
+        if status.is_success() || status == StatusCode::NOT_MODIFIED {
+            Ok(response)
+        } else {
+            Err(format!("the server answered {status}"))
+        }
+    });
+    let response: Response = match response {
+        Ok(response) => response,
+        Err(err) => {
+            // a stale copy is still better than no version list at all
+            if let Some(cached) = cached {
+                write_line(&format!("Using the cached copy of {url}: {err}"));
+                return Ok(cached);
+            }
+            return Err(err.into());
+        }
+    };
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let (Some(mut entry), Some(cached)) = (entry, cached) {
+            entry.fetched_at = now();
+            update_index(entry)?;
+            return Ok(cached);
+        }
+    }
+
+    let response: Response = response.error_for_status()?;
+    let etag: Option<String> = header_value(&response, ETAG);
+    let last_modified: Option<String> = header_value(&response, LAST_MODIFIED);
+
+    let bytes: Vec<u8> = response.bytes().await?.to_vec();
+    write_vec(&bytes, path)?;
+    update_index(MetadataCacheEntry {
+        url: url.to_string(),
+        path: path.to_string(),
+        etag,
+        last_modified,
+        fetched_at: now(),
+    })?;
+
+    Ok(bytes)
+}
+
+pub async fn fetch_metadata_json(
+    url: &str,
+    path: &str,
+    force: bool,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let bytes: Vec<u8> = fetch_metadata(url, path, force).await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Lists every cached metadata file and when it was last fetched.
+pub fn get_cache_entries() -> Vec<MetadataCacheEntry> {
+    let mut entries: Vec<MetadataCacheEntry> = read_index().into_values().collect();
+    entries.sort_by(|a, b| a.url.cmp(&b.url));
+    entries
+}
+
+/// When the server last changed `url` according to its cached `Last-Modified`, in the
+/// `2023-06-12T13:25:51+00:00` format of the version jsons.
+pub fn cached_modified_time(url: &str) -> Option<String> {
+    let last_modified: String = read_index().remove(url)?.last_modified?;
+    http_date_to_rfc3339(&last_modified)
+}
+
+// `Tue, 15 Nov 2022 08:12:31 GMT` -> `2022-11-15T08:12:31+00:00`
+fn http_date_to_rfc3339(date: &str) -> Option<String> {
+    static MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let parts: Vec<&str> = date.split_whitespace().collect();
+    match parts.as_slice() {
+        [_, day, month, year, time, "GMT"] => {
+            let month: usize = MONTHS.iter().position(|name| name == month)? + 1;
+            let day: u32 = day.parse().ok()?;
+            Some(format!("{year}-{month:02}-{day:02}T{time}+00:00"))
+        }
+        _ => None,
+    }
+}
+
+fn header_value(response: &Response, name: reqwest::header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+fn read_index() -> HashMap<String, MetadataCacheEntry> {
+    let _lock = INDEX_LOCK.lock().unwrap();
+    read_index_unlocked()
+}
+
+fn read_index_unlocked() -> HashMap<String, MetadataCacheEntry> {
+    fs::read(get_data_root().join(METADATA_CACHE_INDEX))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn update_index(entry: MetadataCacheEntry) -> Result<(), Box<dyn std::error::Error>> {
+    let _lock = INDEX_LOCK.lock().unwrap();
+    let mut index: HashMap<String, MetadataCacheEntry> = read_index_unlocked();
+    index.insert(entry.url.clone(), entry);
+    write_value(&index, METADATA_CACHE_INDEX)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::utils::test_server::{serve, Reply};
+
+    #[test]
+    fn converts_http_dates() {
+        assert_eq!(
+            http_date_to_rfc3339("Tue, 15 Nov 2022 08:12:31 GMT").as_deref(),
+            Some("2022-11-15T08:12:31+00:00")
+        );
+        assert_eq!(http_date_to_rfc3339("Tue, 15 Foo 2022 08:12:31 GMT"), None);
+        assert_eq!(http_date_to_rfc3339("2022-11-15"), None);
+    }
+
+    fn manifest_reply() -> Reply {
+        Reply {
+            status: 200,
+            headers: vec![("ETag", String::from("\"v1\""))],
+            body: b"{\"latest\":1}".to_vec(),
+        }
+    }
+
+    fn cache_path() -> String {
+        format!("atlas-cache-{}/manifest.json", rand::random::<u32>())
+    }
+
+    fn remove(path: &str) {
+        let _ = fs::remove_dir_all(get_data_root().join(path).parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn fresh_copies_are_reused_until_the_ttl_expires() {
+        let (url, requests) = serve(vec![manifest_reply(), Reply::status(304)]).await;
+        let url: String = format!("{url}/manifest.json");
+        let path: String = cache_path();
+
+        let fetched: Vec<u8> = fetch_metadata(&url, &path, false).await.unwrap();
+        let reused: Vec<u8> = fetch_metadata(&url, &path, false).await.unwrap();
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        // once stale it is revalidated with the etag, a 304 keeps the copy
+        let mut entry: MetadataCacheEntry = read_index().remove(&url).unwrap();
+        entry.fetched_at = 0;
+        update_index(entry).unwrap();
+        let revalidated: Vec<u8> = fetch_metadata(&url, &path, false).await.unwrap();
+        let entry: MetadataCacheEntry = read_index().remove(&url).unwrap();
+        remove(&path);
+
+        assert_eq!(fetched, b"{\"latest\":1}");
+        assert_eq!(reused, fetched);
+        assert_eq!(revalidated, fetched);
+        assert!(entry.fetched_at > 0);
+        let requests: Vec<String> = requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].to_lowercase().contains("if-none-match: \"v1\""));
+    }
+
+    #[tokio::test]
+    async fn error_statuses_fall_back_to_the_cached_copy() {
+        let (url, _) = serve(vec![manifest_reply(), Reply::status(503)]).await;
+        let url: String = format!("{url}/manifest.json");
+        let path: String = cache_path();
+
+        let fetched: Vec<u8> = fetch_metadata(&url, &path, false).await.unwrap();
+        let fallback: Result<Vec<u8>, String> = fetch_metadata(&url, &path, true)
+            .await
+            .map_err(|err| err.to_string());
+        remove(&path);
+
+        assert_eq!(fallback.unwrap(), fetched);
+    }
+
+    #[tokio::test]
+    async fn error_statuses_fail_without_a_cached_copy() {
+        let (url, _) = serve(vec![Reply::status(500)]).await;
+        let path: String = cache_path();
+
+        let result: Result<Vec<u8>, String> =
+            fetch_metadata(&format!("{url}/manifest.json"), &path, false)
+                .await
+                .map_err(|err| err.to_string());
+        remove(&path);
+
+        assert!(result.is_err());
+        assert!(!get_data_root().join(&path).exists());
+    }
+}
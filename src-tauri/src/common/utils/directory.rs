@@ -1,4 +1,8 @@
-use std::{env, fs, path::PathBuf};
+use std::{
+    env, fs,
+    io::{Error, ErrorKind},
+    path::{Component, Path, PathBuf},
+};
 
 pub async fn check_directory(path: &str) -> PathBuf {
     check_directory_sync(path)
@@ -17,3 +21,210 @@ pub fn check_directory_sync(path: &str) -> PathBuf {
     fs::create_dir_all(&path).expect("Failed to create directory");
     path
 }
+
+pub fn get_data_root() -> PathBuf {
+    env::current_exe()
+        .expect("Failed to get executable path")
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+/// Joins an untrusted relative path (zip entry names, manifest supplied paths...) to `base`.
+/// Absolute paths, `..` components and symlinks below `base` pointing outside of it are
+/// rejected. `base` itself may be a symlink, it is resolved first.
+pub fn safe_join(base: &Path, untrusted: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let joined: PathBuf = join_relative(base, untrusted)?;
+    check_symlinks(base, &joined)?;
+
+    Ok(joined)
+}
+
+// only keeps the normal components of `untrusted`, anything climbing out of `base` is an error
+fn join_relative(base: &Path, untrusted: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let mut joined: PathBuf = base.to_path_buf();
+
+    for component in Path::new(&untrusted.replace('\\', "/")).components() {
+        match component {
+            Component::Normal(part) => joined.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(Box::new(Error::new(
+                    ErrorKind::Other,
+                    format!(
+                        "Refusing to write outside of {}: {untrusted}",
+                        base.display()
+                    ),
+                )));
+            }
+        }
+    }
+
+    Ok(joined)
+}
+
+/// Resolves a path used by the launcher to a location inside the data root. Relative paths
+/// are joined to the data root, absolute paths are only accepted if they already point inside it.
+/// The launcher never creates symlinks, so the ones in the data root (a shared `instances` or
+/// `saves` folder on another drive) are the user's own and are followed.
+pub fn resolve_data_path(path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let root: PathBuf = get_data_root();
+    let path_buf: PathBuf = PathBuf::from(path);
+
+    if path_buf.is_absolute() {
+        let relative: &Path = match path_buf.strip_prefix(&root) {
+            Ok(relative) => relative,
+            Err(_) => {
+                return Err(Box::new(Error::new(
+                    ErrorKind::Other,
+                    format!("Refusing to write outside of the data directory: {path}"),
+                )));
+            }
+        };
+        return join_relative(&root, relative.to_str().unwrap_or_default());
+    }
+
+    join_relative(&root, path)
+}
+
+// walks up to the deepest existing ancestor and makes sure it still resolves inside base
+fn check_symlinks(base: &Path, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let canonical_base: PathBuf = match fs::canonicalize(base) {
+        Ok(canonical_base) => canonical_base,
+        Err(_) => return Ok(()),
+    };
+
+    let mut current: &Path = path;
+    loop {
+        if let Ok(metadata) = fs::symlink_metadata(current) {
+            let resolved: PathBuf = match fs::canonicalize(current) {
+                Ok(resolved) => resolved,
+                Err(_) if metadata.file_type().is_symlink() => {
+                    return Err(Box::new(Error::new(
+                        ErrorKind::Other,
+                        format!("Refusing to follow dangling symlink {}", current.display()),
+                    )));
+                }
+                Err(err) => return Err(Box::new(err)),
+            };
+            if !resolved.starts_with(&canonical_base) {
+                return Err(Box::new(Error::new(
+                    ErrorKind::Other,
+                    format!("{} escapes {}", path.display(), base.display()),
+                )));
+            }
+            return Ok(());
+        }
+        match current.parent() {
+            Some(parent) if parent.starts_with(base) => current = parent,
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// The entries directly in `path`, none when it can't be read.
+pub fn list_directory(path: &Path) -> Vec<PathBuf> {
+    match fs::read_dir(path) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Every file below `path`, recursively.
+pub fn walk_files(path: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = vec![];
+    for entry in list_directory(path) {
+        if entry.is_dir() {
+            files.extend(walk_files(&entry));
+        } else {
+            files.push(entry);
+        }
+    }
+    files
+}
+
+/// Every file below `path` as a `/` separated relative path, sorted.
+pub fn relative_files(path: &Path) -> Vec<String> {
+    let mut files: Vec<String> = walk_files(path)
+        .iter()
+        .filter_map(|file| file.strip_prefix(path).ok())
+        .filter_map(|file| file.to_str())
+        .map(|file| file.replace('\\', "/"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Whether a relative path is one of `exclude` or inside one of them.
+pub fn is_excluded(path: &str, exclude: &[String]) -> bool {
+    exclude.iter().any(|excluded| {
+        let excluded: &str = excluded.trim_matches('/');
+        path == excluded || path.starts_with(&format!("{excluded}/"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exclusions_match_whole_path_components() {
+        let exclude: Vec<String> = vec![String::from("saves"), String::from("config/secret.toml")];
+        assert!(is_excluded("saves/world/level.dat", &exclude));
+        assert!(is_excluded("config/secret.toml", &exclude));
+        assert!(!is_excluded("savestates/slot1", &exclude));
+        assert!(!is_excluded("config/other.toml", &exclude));
+    }
+
+    #[test]
+    fn safe_join_rejects_traversal_and_absolute_paths() {
+        let base: &Path = Path::new("/data/instances/pack");
+        assert_eq!(
+            safe_join(base, "mods/./sodium.jar").unwrap(),
+            base.join("mods/sodium.jar")
+        );
+        assert!(safe_join(base, "../x").is_err());
+        assert!(safe_join(base, "mods/../../x").is_err());
+        assert!(safe_join(base, "..\\x").is_err());
+        assert!(safe_join(base, "/etc/x").is_err());
+        assert!(safe_join(base, "\\x").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_only_followed_in_the_base() {
+        let temp: PathBuf = env::temp_dir().join(format!("atlas-join-{}", rand::random::<u32>()));
+        let outside: PathBuf = temp.join("outside");
+        let base: PathBuf = temp.join("base");
+        fs::create_dir_all(&outside).unwrap();
+        fs::create_dir_all(&base).unwrap();
+        std::os::unix::fs::symlink(&outside, base.join("escape")).unwrap();
+        std::os::unix::fs::symlink(&base, temp.join("linked")).unwrap();
+
+        assert!(safe_join(&base, "escape/x").is_err());
+        assert!(safe_join(&base, "escape").is_err());
+        // a base reached through a symlink, like a moved instances folder
+        assert!(safe_join(&temp.join("linked"), "mods/x.jar").is_ok());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn data_paths_follow_the_users_symlinks() {
+        let name: String = format!("atlas-data-{}", rand::random::<u32>());
+        let outside: PathBuf = env::temp_dir().join(&name);
+        let link: PathBuf = get_data_root().join(&name);
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+
+        let resolved: Result<PathBuf, Box<dyn std::error::Error>> =
+            resolve_data_path(&format!("{name}/world/level.dat"));
+        let _ = fs::remove_file(&link);
+        fs::remove_dir_all(&outside).unwrap();
+
+        assert_eq!(resolved.unwrap(), link.join("world/level.dat"));
+        assert!(resolve_data_path("../x").is_err());
+        assert!(resolve_data_path("/etc/passwd").is_err());
+    }
+}
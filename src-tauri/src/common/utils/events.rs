@@ -0,0 +1,25 @@
+use tauri::{AppHandle, Manager};
+
+use crate::data::models::{BaseEventPayload, DownloadInstanceEventPayload};
+
+/// Shows what is being done to `instance_name` while it is still loading.
+pub fn emit_progress(app: &AppHandle, instance_name: &str, message: &str) {
+    emit_status(app, instance_name, message, "Loading");
+}
+
+/// Sends a `download` event without byte counts, `status` is `Loading`, `Success` or `Error`.
+pub fn emit_status(app: &AppHandle, instance_name: &str, message: &str, status: &str) {
+    app.emit_all(
+        "download",
+        DownloadInstanceEventPayload {
+            base: BaseEventPayload {
+                message: message.to_string(),
+                status: status.to_string(),
+            },
+            total: 0,
+            downloaded: 0,
+            name: instance_name.to_string(),
+        },
+    )
+    .unwrap();
+}
@@ -4,13 +4,13 @@ use reqwest::{header::CONTENT_LENGTH, Response};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha1::Sha1;
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha256, Sha512};
 use std::{
-    env,
     fs::{self, File},
     io::{self, Cursor, Read, Write},
     path::Path,
     path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
 };
 use tauri::{AppHandle, Manager};
 use tokio::time::{sleep, Duration};
@@ -19,21 +19,23 @@ use zip::{read::ZipFile, write::FileOptions, ZipArchive, ZipWriter};
 use crate::data::models::{BaseEventPayload, DownloadInstanceEventPayload};
 
 use super::{
-    directory::{self, check_directory_sync},
+    directory::{check_directory_sync, resolve_data_path, safe_join},
     log::write_line,
+    mirror::candidate_urls,
+    network::{ensure_online, is_offline},
 };
 
 pub enum ChecksumType {
     SHA1,
     SHA256,
+    SHA512,
     MD5,
 }
 
 // Read
 
 pub async fn read_as_vec(path: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    let exe_path: PathBuf = env::current_exe().unwrap();
-    let file_path: PathBuf = exe_path.parent().unwrap().join(path);
+    let file_path: PathBuf = resolve_data_path(path)?;
 
     if !file_path.exists() {
         return Err(Box::new(std::io::Error::new(
@@ -51,7 +53,7 @@ pub async fn read_as_value<T>(path: &str) -> Result<T, Box<dyn std::error::Error
 where
     T: for<'de> Deserialize<'de>,
 {
-    let bytes: Vec<u8> = read_as_vec(path).await.unwrap();
+    let bytes: Vec<u8> = read_as_vec(path).await?;
     let result: T = serde_json::from_slice(&bytes)?;
 
     Ok(result)
@@ -60,28 +62,50 @@ where
 // Write
 
 pub fn write_vec(data: &Vec<u8>, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let exe_path: PathBuf = env::current_exe()?;
+    write_atomic(data, path)
+}
+
+/// Writes launcher state as JSON.
+pub fn write_value<T: Serialize>(data: &T, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes: Vec<u8> = serde_json::to_vec(&data)?;
+    write_atomic(&bytes, path)?;
+
+    Ok(())
+}
 
-    let file_path: PathBuf = exe_path.parent().unwrap().join(path);
+/// Every write is flushed to disk before returning, a manifest or library cut short by a crash
+/// would otherwise look valid until it is parsed.
+fn write_atomic(data: &[u8], path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let file_path: PathBuf = resolve_data_path(path)?;
     let path: &Path = file_path.parent().unwrap();
-    directory::check_directory_sync(path.to_str().unwrap());
+    fs::create_dir_all(path)?;
 
-    let mut file: File = File::create(&file_path)?;
+    // write next to the target and rename over it so a crash never leaves a truncated file
+    let file_name: &str = file_path.file_name().unwrap().to_str().unwrap_or_default();
+    let temp_path: PathBuf = path.join(format!(".{file_name}.{:08x}.tmp", rand::random::<u32>()));
 
+    let mut file: File = File::create(&temp_path)?;
     file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
 
-    Ok(())
-}
+    if let Err(err) = fs::rename(&temp_path, &file_path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(Box::new(err));
+    }
 
-pub fn write_value<T: Serialize>(data: &T, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let bytes: Vec<u8> = serde_json::to_vec(&data).unwrap();
-    write_vec(&bytes, path)?;
+    // persist the rename itself, directories can't be opened for syncing on windows
+    #[cfg(unix)]
+    File::open(path)?.sync_all()?;
 
     Ok(())
 }
 
 // Download
 
+// a corrupt download is fetched again this many times before giving up
+static CHECKSUM_RETRIES: u8 = 2;
+
 pub async fn download_as_vec(
     url: &str,
     checksum: &str,
@@ -91,7 +115,8 @@ pub async fn download_as_vec(
     force: bool,
     instance: Option<(&AppHandle, &str)>,
 ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    if !force {
+    // while offline, whatever is stored locally is the freshest copy we can get
+    if !force || is_offline() {
         let mut vec: Option<Vec<u8>> = None;
 
         match read_as_vec(path).await {
@@ -121,6 +146,8 @@ pub async fn download_as_vec(
         }
     }
 
+    ensure_online()?;
+
     let mut retry_count: u8 = 0;
     let bytes: Vec<u8>;
 
@@ -133,11 +160,19 @@ pub async fn download_as_vec(
         item_name = "Java 17";
     }
 
+    let urls: Vec<String> = candidate_urls(url).await;
+    let mut candidate: usize = 0;
+
     loop {
+        let url: &str = &urls[candidate];
         let response: Response = match reqwest::get(url).await {
             Ok(response) => response,
             Err(error) => {
                 if retry_count >= 5 {
+                    if next_candidate(&urls, &mut candidate, &error.to_string()) {
+                        retry_count = 0;
+                        continue;
+                    }
                     return Err(Box::new(error));
                 }
                 retry_count += 1;
@@ -147,6 +182,14 @@ pub async fn download_as_vec(
             }
         };
 
+        if !response.status().is_success() {
+            if next_candidate(&urls, &mut candidate, &response.status().to_string()) {
+                retry_count = 0;
+                continue;
+            }
+            return Err(format!("{url} answered {}", response.status()).into());
+        }
+
         let content_length: Option<u64> = response
             .headers()
             .get(CONTENT_LENGTH)
@@ -211,6 +254,13 @@ pub async fn download_as_vec(
             bytes = current_bytes;
             break;
         }
+        if next_candidate(&urls, &mut candidate, "checksum mismatch") {
+            retry_count = 0;
+            continue;
+        }
+        if retry_count >= CHECKSUM_RETRIES {
+            return Err(format!("The checksum of {url} doesn't match {checksum}").into());
+        }
         retry_count += 1;
         sleep(Duration::from_secs((1 + retry_count).into())).await;
     }
@@ -218,7 +268,8 @@ pub async fn download_as_vec(
     if path != "" {
         if extract {
             let mut reader: Cursor<Vec<u8>> = Cursor::new(bytes.to_vec());
-            let path: PathBuf = check_directory_sync(path);
+            let path: PathBuf = resolve_data_path(path)?;
+            fs::create_dir_all(&path)?;
             extract_zip(path, &mut reader).await?;
         } else {
             write_vec(&bytes.to_vec(), path)?;
@@ -228,6 +279,20 @@ pub async fn download_as_vec(
     Ok(bytes.to_vec())
 }
 
+// moves on to the next url (usually from a mirror back to the original host) if there is one left
+fn next_candidate(urls: &[String], candidate: &mut usize, reason: &str) -> bool {
+    if *candidate + 1 >= urls.len() {
+        return false;
+    }
+    write_line(&format!(
+        "Falling back from {} to {}: {reason}",
+        urls[*candidate],
+        urls[*candidate + 1]
+    ));
+    *candidate += 1;
+    true
+}
+
 pub async fn download_as_json(
     url: &str,
     checksum: &str,
@@ -243,32 +308,13 @@ pub async fn download_as_json(
     Ok(json)
 }
 
-pub async fn download_as_string(
-    url: &str,
-    checksum: &str,
-    checksum_type: &ChecksumType,
-    path: &str,
-    extract: bool,
-    force: bool,
-    instance: Option<(&AppHandle, &str)>,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let vec: Vec<u8> =
-        download_as_vec(url, checksum, checksum_type, path, extract, force, instance).await?;
-    let string: String = String::from_utf8(vec)?;
-    Ok(string)
-}
-
 // Verify
 
-pub async fn verify_hash(
-    checksum: &str,
+/// The hex digest of `data`.
+pub fn checksum_of(
     checksum_type: &ChecksumType,
     data: &[u8],
-) -> Result<bool, Box<dyn std::error::Error>> {
-    if checksum.is_empty() {
-        return Ok(true);
-    };
-
+) -> Result<String, Box<dyn std::error::Error>> {
     let actual_checksum: String;
 
     let mut bytes: &[u8] = data;
@@ -284,12 +330,31 @@ pub async fn verify_hash(
             io::copy(&mut bytes, &mut hasher)?;
             actual_checksum = format!("{:x}", hasher.finalize());
         }
+        ChecksumType::SHA512 => {
+            let mut hasher = Sha512::new();
+            io::copy(&mut bytes, &mut hasher)?;
+            actual_checksum = format!("{:x}", hasher.finalize());
+        }
         ChecksumType::MD5 => {
             let digest: md5::Digest = md5::compute(&mut bytes);
             actual_checksum = format!("{:x}", digest);
         }
     }
 
+    Ok(actual_checksum)
+}
+
+pub async fn verify_hash(
+    checksum: &str,
+    checksum_type: &ChecksumType,
+    data: &[u8],
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if checksum.is_empty() {
+        return Ok(true);
+    };
+
+    let actual_checksum: String = checksum_of(checksum_type, data)?;
+
     if actual_checksum != checksum {
         return Ok(false);
     };
@@ -307,13 +372,15 @@ pub async fn extract_zip(
 
     for i in 0..archive.len() {
         let mut file: ZipFile = archive.by_index(i)?;
-        let outpath: PathBuf = path.join(file.mangled_name());
 
         if (&*file.name()).starts_with("META-INF/") {
             // Skip extracting the file if it's inside META-INF/
             continue;
         }
 
+        // entry names come from remote archives, never trust them
+        let outpath: PathBuf = safe_join(&path, file.name())?;
+
         if (&*file.name()).ends_with('/') {
             fs::create_dir_all(&outpath)?;
         } else {
@@ -333,7 +400,13 @@ pub async fn extract_file(
     source: &mut Cursor<Vec<u8>>,
     filename: &str,
 ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    let mut archive: ZipArchive<&mut Cursor<Vec<u8>>> = ZipArchive::new(source).unwrap();
+    let mut archive: ZipArchive<&mut Cursor<Vec<u8>>> = ZipArchive::new(source)?;
+    // an exact path wins over the first entry that merely contains it
+    if let Ok(mut file) = archive.by_name(filename) {
+        let mut content: Vec<u8> = Vec::new();
+        file.read_to_end(&mut content)?;
+        return Ok(content);
+    }
     for i in 0..archive.len() {
         let mut file: ZipFile = archive.by_index(i)?;
 
@@ -350,10 +423,44 @@ pub async fn extract_file(
     )));
 }
 
+// Quarantine
+
+/// Moves a file that can't be parsed out of the way so the rest of the listing keeps working.
+pub fn quarantine(path: &Path) {
+    let timestamp: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let file_name: &str = path
+        .file_name()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap_or_default();
+    let target: PathBuf = path.with_file_name(format!("{file_name}.corrupt-{timestamp}"));
+
+    match fs::rename(path, &target) {
+        Ok(_) => write_line(&format!(
+            "Quarantined corrupt file {} as {}",
+            path.display(),
+            target.display()
+        )),
+        Err(err) => write_line(&format!(
+            "Failed to quarantine corrupt file {}: {err}",
+            path.display()
+        )),
+    }
+}
+
 // Delete
 
 pub fn delete(path: &str) {
-    let path: PathBuf = env::current_exe().unwrap().parent().unwrap().join(path);
+    let path: PathBuf = match resolve_data_path(path) {
+        Ok(path) => path,
+        Err(err) => {
+            write_line(&format!("Not deleting {path}: {err}"));
+            return;
+        }
+    };
 
     if !path.exists() {
         return;
@@ -442,9 +549,173 @@ pub fn library_name_to_raw_path(name: &str) -> String {
 }
 
 pub fn library_name_to_path(name: &str) -> String {
-    let libraries_path: String = check_directory_sync("libraries")
+    check_directory_sync("libraries")
+        .join(library_name_to_raw_path(name))
         .to_str()
         .unwrap()
-        .to_string();
-    libraries_path.clone() + "\\" + &library_name_to_raw_path(name).replace("/", "\\")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::utils::{
+        directory::get_data_root,
+        test_server::{serve, Reply},
+    };
+
+    fn zip_of(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive: ZipWriter<Cursor<Vec<u8>>> = ZipWriter::new(Cursor::new(vec![]));
+        for (name, contents) in entries {
+            archive.start_file(*name, FileOptions::default()).unwrap();
+            archive.write_all(contents).unwrap();
+        }
+        archive.finish().unwrap().into_inner()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("atlas-{name}-{}", rand::random::<u32>()));
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn extract_zip_keeps_crafted_entries_inside() {
+        let temp: PathBuf = temp_dir("extract");
+        let target: PathBuf = temp.join("target");
+        fs::create_dir_all(&target).unwrap();
+
+        let traversal: Vec<u8> = zip_of(&[("ok.txt", b"ok"), ("../x", b"escaped")]);
+        assert!(extract_zip(target.clone(), &mut Cursor::new(traversal))
+            .await
+            .is_err());
+        assert!(target.join("ok.txt").is_file());
+        assert!(!temp.join("x").exists());
+
+        let absolute_path: String = temp.join("absolute").to_str().unwrap().to_string();
+        let absolute: Vec<u8> = zip_of(&[(&absolute_path, b"escaped")]);
+        assert!(extract_zip(target.clone(), &mut Cursor::new(absolute))
+            .await
+            .is_err());
+        assert!(!temp.join("absolute").exists());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn extract_zip_never_writes_through_symlinks() {
+        let temp: PathBuf = temp_dir("symlink");
+        let outside: PathBuf = temp.join("outside");
+        let target: PathBuf = temp.join("target");
+        fs::create_dir_all(&outside).unwrap();
+        fs::create_dir_all(&target).unwrap();
+
+        // a symlink entry followed by a file below it
+        let mut archive: ZipWriter<Cursor<Vec<u8>>> = ZipWriter::new(Cursor::new(vec![]));
+        archive
+            .add_symlink("link", outside.to_str().unwrap(), FileOptions::default())
+            .unwrap();
+        archive
+            .start_file("link/x", FileOptions::default())
+            .unwrap();
+        archive.write_all(b"escaped").unwrap();
+        let crafted: Vec<u8> = archive.finish().unwrap().into_inner();
+        let _ = extract_zip(target.clone(), &mut Cursor::new(crafted)).await;
+        assert!(!outside.join("x").exists());
+
+        // a symlinked parent that is already in the target folder
+        fs::remove_dir_all(&target).unwrap();
+        fs::create_dir_all(&target).unwrap();
+        std::os::unix::fs::symlink(&outside, target.join("parent")).unwrap();
+        let crafted: Vec<u8> = zip_of(&[("parent/x", b"escaped")]);
+        assert!(extract_zip(target.clone(), &mut Cursor::new(crafted))
+            .await
+            .is_err());
+        assert!(!outside.join("x").exists());
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[tokio::test]
+    async fn downloaded_archives_are_extracted_inside_the_target() {
+        let folder: String = format!("atlas-download-{}", rand::random::<u32>());
+        let crafted: Vec<u8> = zip_of(&[("natives/ok.so", b"ok"), ("../../x", b"escaped")]);
+        let valid: Vec<u8> = zip_of(&[("natives/ok.so", b"ok")]);
+        let (url, _) = serve(vec![Reply::ok(&crafted), Reply::ok(&valid)]).await;
+
+        let path: String = format!("{folder}/target");
+        let crafted_result: Result<Vec<u8>, Box<dyn std::error::Error>> = download_as_vec(
+            &format!("{url}/crafted.zip"),
+            "",
+            &ChecksumType::SHA1,
+            &path,
+            true,
+            true,
+            None,
+        )
+        .await;
+        let valid_result: Result<Vec<u8>, Box<dyn std::error::Error>> = download_as_vec(
+            &format!("{url}/valid.zip"),
+            "",
+            &ChecksumType::SHA1,
+            &path,
+            true,
+            true,
+            None,
+        )
+        .await;
+
+        let root: PathBuf = get_data_root();
+        let escaped: bool = root.join("x").exists() || root.join(&folder).join("x").exists();
+        let extracted: bool = root.join(&path).join("natives/ok.so").is_file();
+        let _ = fs::remove_dir_all(root.join(&folder));
+
+        assert!(crafted_result.is_err());
+        assert!(!escaped);
+        assert!(valid_result.is_ok());
+        assert!(extracted);
+    }
+
+    #[tokio::test]
+    async fn downloads_fail_on_error_statuses_and_bad_checksums() {
+        let (url, requests) = serve(vec![
+            Reply::status(404),
+            Reply::ok(b"corrupt"),
+            Reply::ok(b"corrupt"),
+            Reply::ok(b"corrupt"),
+        ])
+        .await;
+
+        let missing: Result<Vec<u8>, String> = download_as_vec(
+            &format!("{url}/missing.jar"),
+            "",
+            &ChecksumType::SHA1,
+            "",
+            false,
+            true,
+            None,
+        )
+        .await
+        .map_err(|err| err.to_string());
+        assert!(missing.unwrap_err().contains("404"));
+
+        let corrupt: Result<Vec<u8>, String> = download_as_vec(
+            &format!("{url}/corrupt.jar"),
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+            &ChecksumType::SHA1,
+            "",
+            false,
+            true,
+            None,
+        )
+        .await
+        .map_err(|err| err.to_string());
+        assert!(corrupt.is_err());
+        assert_eq!(
+            requests.lock().unwrap().len(),
+            2 + CHECKSUM_RETRIES as usize
+        );
+    }
 }
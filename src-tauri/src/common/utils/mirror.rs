@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use crate::data::{
+    constants::{
+        ADOPTIUM_API_BASE, CURSEFORGE_API_BASE, FABRIC_MAVEN_BASE, FABRIC_META_BASE,
+        FORGE_FILES_BASE, FORGE_MAVEN_BASE, MINECRAFT_LIBRARIES_BASE, MINECRAFT_RESOURCES_BASE,
+        MODRINTH_API_ORIGIN, MOJANG_DATA_BASE, MOJANG_LAUNCHER_BASE, MOJANG_LAUNCHER_META_BASE,
+        MOJANG_META_BASE, NEOFORGE_MAVEN_BASE, QUILT_MAVEN_BASE, QUILT_META_BASE,
+    },
+    models::MirrorSource,
+};
+
+use super::settings::get_settings;
+
+static SOURCES: [MirrorSource; 16] = [
+    MirrorSource::MojangMeta,
+    MirrorSource::MojangData,
+    MirrorSource::MojangLauncherMeta,
+    MirrorSource::MojangLauncher,
+    MirrorSource::MojangResources,
+    MirrorSource::MojangLibraries,
+    MirrorSource::ForgeMaven,
+    MirrorSource::ForgeFiles,
+    MirrorSource::NeoForgeMaven,
+    MirrorSource::FabricMeta,
+    MirrorSource::FabricMaven,
+    MirrorSource::QuiltMeta,
+    MirrorSource::QuiltMaven,
+    MirrorSource::Adoptium,
+    MirrorSource::Modrinth,
+    MirrorSource::CurseForge,
+];
+
+/// The default base url of a remote source.
+pub fn origin(source: MirrorSource) -> &'static str {
+    match source {
+        MirrorSource::MojangMeta => MOJANG_META_BASE,
+        MirrorSource::MojangData => MOJANG_DATA_BASE,
+        MirrorSource::MojangLauncherMeta => MOJANG_LAUNCHER_META_BASE,
+        MirrorSource::MojangLauncher => MOJANG_LAUNCHER_BASE,
+        MirrorSource::MojangResources => MINECRAFT_RESOURCES_BASE,
+        MirrorSource::MojangLibraries => MINECRAFT_LIBRARIES_BASE,
+        MirrorSource::ForgeMaven => FORGE_MAVEN_BASE,
+        MirrorSource::ForgeFiles => FORGE_FILES_BASE,
+        MirrorSource::NeoForgeMaven => NEOFORGE_MAVEN_BASE,
+        MirrorSource::FabricMeta => FABRIC_META_BASE,
+        MirrorSource::FabricMaven => FABRIC_MAVEN_BASE,
+        MirrorSource::QuiltMeta => QUILT_META_BASE,
+        MirrorSource::QuiltMaven => QUILT_MAVEN_BASE,
+        MirrorSource::Adoptium => ADOPTIUM_API_BASE,
+        MirrorSource::Modrinth => MODRINTH_API_ORIGIN,
+        MirrorSource::CurseForge => CURSEFORGE_API_BASE,
+    }
+}
+
+/// Urls to try for `url`, in order: the configured mirror first and the original url as fallback.
+pub async fn candidate_urls(url: &str) -> Vec<String> {
+    let mirrors: HashMap<MirrorSource, String> = get_settings().await.mirrors;
+    let mut urls: Vec<String> = vec![];
+
+    if let Some(mirrored) = mirror_url(url, &mirrors) {
+        urls.push(mirrored);
+    }
+    urls.push(url.to_string());
+
+    urls
+}
+
+fn mirror_url(url: &str, mirrors: &HashMap<MirrorSource, String>) -> Option<String> {
+    for source in SOURCES {
+        let rest: &str = match url.strip_prefix(origin(source)) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('?') => rest,
+            _ => continue,
+        };
+        let mirror: &str = mirrors.get(&source)?.trim_end_matches('/');
+        if mirror.is_empty() {
+            return None;
+        }
+        return Some(format!("{mirror}{rest}"));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::constants::MINECRAFT_VERSION_MANIFEST;
+
+    fn mirrors() -> HashMap<MirrorSource, String> {
+        HashMap::from([
+            (
+                MirrorSource::MojangMeta,
+                String::from("https://mirror.example/mojang/"),
+            ),
+            (
+                MirrorSource::Modrinth,
+                String::from("https://mirror.example/modrinth"),
+            ),
+            (MirrorSource::FabricMeta, String::new()),
+        ])
+    }
+
+    #[test]
+    fn rewrites_the_origin_of_mirrored_sources() {
+        assert_eq!(
+            mirror_url(MINECRAFT_VERSION_MANIFEST, &mirrors()).as_deref(),
+            Some("https://mirror.example/mojang/mc/game/version_manifest_v2.json")
+        );
+        assert_eq!(
+            mirror_url(
+                &format!("{MODRINTH_API_ORIGIN}/v2/search?query=sodium"),
+                &mirrors()
+            )
+            .as_deref(),
+            Some("https://mirror.example/modrinth/v2/search?query=sodium")
+        );
+        assert_eq!(
+            mirror_url(MODRINTH_API_ORIGIN, &mirrors()).as_deref(),
+            Some("https://mirror.example/modrinth")
+        );
+    }
+
+    #[test]
+    fn leaves_other_urls_alone() {
+        // no mirror configured, an empty one, or a host that only starts like the origin
+        assert_eq!(
+            mirror_url(&format!("{FORGE_MAVEN_BASE}/net/x.jar"), &mirrors()),
+            None
+        );
+        assert_eq!(
+            mirror_url(&format!("{FABRIC_META_BASE}/v2/versions"), &mirrors()),
+            None
+        );
+        assert_eq!(
+            mirror_url(&format!("{MOJANG_META_BASE}.evil.net/x.json"), &mirrors()),
+            None
+        );
+    }
+}
@@ -1,3 +1,10 @@
+pub mod cache;
 pub mod directory;
+pub mod events;
 pub mod file;
-pub mod log;
\ No newline at end of file
+pub mod log;
+pub mod mirror;
+pub mod network;
+pub mod settings;
+#[cfg(test)]
+pub mod test_server;
@@ -0,0 +1,65 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use reqwest::Client;
+use tokio::time::Duration;
+
+use crate::data::constants::MINECRAFT_VERSION_MANIFEST;
+
+use super::{log::write_line, mirror::candidate_urls, settings::get_settings};
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Fails with a readable error when the launcher is not allowed to use the network.
+pub fn ensure_online() -> Result<(), Box<dyn std::error::Error>> {
+    if is_offline() {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "The launcher is in offline mode, downloads are disabled.",
+        )));
+    }
+    Ok(())
+}
+
+/// Decides whether the launcher starts offline, either because the user asked for it
+/// or because the metadata servers can't be reached.
+pub async fn detect_offline_mode() -> bool {
+    if get_settings().await.offline_mode {
+        set_offline(true);
+        return true;
+    }
+
+    let client: Client = match Client::builder().timeout(Duration::from_secs(5)).build() {
+        Ok(client) => client,
+        Err(_) => return is_offline(),
+    };
+
+    // reaching either the mirror or the original host is enough to stay online
+    let mut offline: bool = false;
+    for url in candidate_urls(MINECRAFT_VERSION_MANIFEST).await {
+        offline = match client.head(&url).send().await {
+            Ok(_) => false,
+            Err(err) if err.is_connect() || err.is_timeout() => {
+                write_line(&format!("Could not reach {url}: {err}"));
+                true
+            }
+            Err(_) => false,
+        };
+        if !offline {
+            break;
+        }
+    }
+    if offline {
+        write_line("No connection, switching to offline mode");
+    }
+
+    set_offline(offline);
+    offline
+}
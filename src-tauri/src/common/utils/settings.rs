@@ -0,0 +1,23 @@
+use std::sync::RwLock;
+
+use crate::data::{constants::LAUNCHER_SETTINGS, models::LauncherSettings};
+
+use super::file::{read_as_value, write_value};
+
+// every download looks up its mirror, so keep the settings in memory after the first read
+static SETTINGS: RwLock<Option<LauncherSettings>> = RwLock::new(None);
+
+pub async fn get_settings() -> LauncherSettings {
+    if let Some(settings) = SETTINGS.read().unwrap().as_ref() {
+        return settings.clone();
+    }
+    let settings: LauncherSettings = read_as_value(LAUNCHER_SETTINGS).await.unwrap_or_default();
+    *SETTINGS.write().unwrap() = Some(settings.clone());
+    settings
+}
+
+pub fn write_settings(settings: &LauncherSettings) -> Result<(), Box<dyn std::error::Error>> {
+    write_value(settings, LAUNCHER_SETTINGS)?;
+    *SETTINGS.write().unwrap() = Some(settings.clone());
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! A local stand-in for the remote hosts, so downloads can be tested without a network.
+
+use std::sync::{Arc, Mutex};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+pub struct Reply {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Reply {
+    pub fn ok(body: &[u8]) -> Reply {
+        Reply {
+            status: 200,
+            headers: vec![],
+            body: body.to_vec(),
+        }
+    }
+
+    pub fn status(status: u16) -> Reply {
+        Reply {
+            status,
+            headers: vec![],
+            body: format!("status {status}").into_bytes(),
+        }
+    }
+}
+
+/// Answers one connection per reply, in order. Returns the base url and the head of every
+/// request received so far.
+pub async fn serve(replies: Vec<Reply>) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener: TcpListener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url: String = format!("http://{}", listener.local_addr().unwrap());
+    let requests: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+
+    let received: Arc<Mutex<Vec<String>>> = requests.clone();
+    tokio::spawn(async move {
+        for reply in replies {
+            let (mut stream, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(_) => return,
+            };
+
+            let mut request: Vec<u8> = vec![];
+            let mut buffer: [u8; 1024] = [0; 1024];
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                match stream.read(&mut buffer).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => request.extend_from_slice(&buffer[..read]),
+                }
+            }
+            received
+                .lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&request).to_string());
+
+            let mut response: Vec<u8> = format!(
+                "HTTP/1.1 {} Reply\r\nContent-Length: {}\r\nConnection: close\r\n",
+                reply.status,
+                reply.body.len()
+            )
+            .into_bytes();
+            for (name, value) in &reply.headers {
+                response.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+            }
+            response.extend_from_slice(b"\r\n");
+            response.extend_from_slice(&reply.body);
+            let _ = stream.write_all(&response).await;
+            let _ = stream.shutdown().await;
+        }
+    });
+
+    (url, requests)
+}
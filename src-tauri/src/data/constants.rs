@@ -14,13 +14,37 @@ pub static EXTRA_VERSION_MANIFEST: &str =
 
 pub static FORGE_VERSION_MANFIEST: &str =
     "https://files.minecraftforge.net/net/minecraftforge/forge/maven-metadata.json";
+pub static FORGE_PROMOTIONS: &str =
+    "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
 pub static EXTRA_FORGE_VERSION_MANIFEST: &str =
     "https://raw.githubusercontent.com/J0R6IT0/AtlasLauncherResources/main/meta/net.minecraftforge/version_manifest.json";
 
-pub static FABRIC_VERSION_MANIFEST: &str = "https://meta.fabricmc.net/v1/versions";
+pub static FABRIC_VERSION_MANIFEST: &str = "https://meta.fabricmc.net/v2/versions";
 
 pub static QUILT_VERSION_MANIFEST: &str = "https://meta.quiltmc.org/v3/versions";
 
+pub static LITELOADER_VERSION_MANIFEST: &str = "https://dl.liteloader.com/versions/versions.json";
+
+pub static MOJANG_META_BASE: &str = "https://piston-meta.mojang.com";
+pub static MOJANG_DATA_BASE: &str = "https://piston-data.mojang.com";
+pub static MOJANG_LAUNCHER_META_BASE: &str = "https://launchermeta.mojang.com";
+pub static MOJANG_LAUNCHER_BASE: &str = "https://launcher.mojang.com";
+pub static MINECRAFT_LIBRARIES_BASE: &str = "https://libraries.minecraft.net";
+pub static MINECRAFT_RESOURCES_BASE: &str = "https://resources.download.minecraft.net";
+pub static FORGE_MAVEN_BASE: &str = "https://maven.minecraftforge.net";
+pub static NEOFORGE_MAVEN_BASE: &str = "https://maven.neoforged.net";
+pub static NEOFORGE_VERSION_MANIFEST: &str =
+    "https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge";
+pub static FORGE_FILES_BASE: &str = "https://files.minecraftforge.net";
+pub static LITELOADER_BASE: &str = "https://dl.liteloader.com";
+pub static FABRIC_META_BASE: &str = "https://meta.fabricmc.net";
+pub static FABRIC_MAVEN_BASE: &str = "https://maven.fabricmc.net";
+pub static QUILT_META_BASE: &str = "https://meta.quiltmc.org";
+pub static QUILT_MAVEN_BASE: &str = "https://maven.quiltmc.org";
+pub static ADOPTIUM_API_BASE: &str = "https://api.adoptium.net";
+pub static ATLAS_RESOURCES_BASE: &str =
+    "https://github.com/J0R6IT0/AtlasLauncherResources/raw/main";
+
 // local
 pub static NET_MINECRAFT_VERSION_MANIFEST: &str =
     "launcher/meta/net.minecraft/version_manifest.json";
@@ -28,7 +52,30 @@ pub static NET_MINECRAFTFORGE_VERSION_MANIFEST: &str =
     "launcher/meta/net.minecraftforge/version_manifest.json";
 pub static NET_FABRICMC_VERSION_MANIFEST: &str = "launcher/meta/net.fabricmc/version_manifest.json";
 pub static ORG_QUILTMC_VERSION_MANIFEST: &str = "launcher/meta/org.quiltmc/version_manifest.json";
+pub static COM_MUMFREY_LITELOADER_VERSION_MANIFEST: &str =
+    "launcher/meta/com.mumfrey.liteloader/version_manifest.json";
+pub static NET_NEOFORGED_VERSION_MANIFEST: &str =
+    "launcher/meta/net.neoforged/version_manifest.json";
+pub static LAUNCHER_SETTINGS: &str = "launcher/settings.json";
+pub static METADATA_CACHE_INDEX: &str = "launcher/meta/cache.json";
+pub static NET_MINECRAFT_MOJANG_VERSION_MANIFEST: &str =
+    "launcher/meta/net.minecraft/version_manifest_mojang.json";
+pub static NET_MINECRAFT_BETTER_JSONS_VERSION_MANIFEST: &str =
+    "launcher/meta/net.minecraft/version_manifest_betterjsons.json";
+pub static NET_MINECRAFT_EXTRA_VERSION_MANIFEST: &str =
+    "launcher/meta/net.minecraft/version_manifest_extra.json";
+pub static NET_MINECRAFTFORGE_MAVEN_METADATA: &str =
+    "launcher/meta/net.minecraftforge/maven-metadata.json";
+pub static NET_MINECRAFTFORGE_PROMOTIONS: &str =
+    "launcher/meta/net.minecraftforge/promotions_slim.json";
+pub static NET_MINECRAFTFORGE_EXTRA_VERSION_MANIFEST: &str =
+    "launcher/meta/net.minecraftforge/version_manifest_extra.json";
 
 // modplatform
+pub static MODRINTH_API_ORIGIN: &str = "https://api.modrinth.com";
 pub static MODRINTH_BASE: &str = "https://api.modrinth.com/v2";
+pub static CURSEFORGE_API_BASE: &str = "https://api.curseforge.com";
 pub static USER_AGENT: &str = "J0R6IT0/AtlasLauncher/1.0 (jorgeparpar@gmail.com)";
+
+// java
+pub static CLASSPATH_SEPARATOR: &str = if cfg!(windows) { ";" } else { ":" };
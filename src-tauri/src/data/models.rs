@@ -1,3 +1,5 @@
+use std::{collections::HashMap, fmt};
+
 use serde::{Deserialize, Serialize};
 
 // Minecraft
@@ -7,6 +9,228 @@ pub struct MinecraftVersionData {
     pub id: String,
     pub url: String,
     pub r#type: String,
+    pub release_time: String,
+    pub java_version: u8,
+    pub source: VersionSource,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionType {
+    Release,
+    Snapshot,
+    OldBeta,
+    OldAlpha,
+    AprilFools,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModLoaderType {
+    Forge,
+    NeoForge,
+    Fabric,
+    Quilt,
+    OptiFine,
+    LiteLoader,
+}
+
+impl ModLoaderType {
+    pub const ALL: [ModLoaderType; 6] = [
+        ModLoaderType::Forge,
+        ModLoaderType::NeoForge,
+        ModLoaderType::Fabric,
+        ModLoaderType::Quilt,
+        ModLoaderType::OptiFine,
+        ModLoaderType::LiteLoader,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModLoaderType::Forge => "forge",
+            ModLoaderType::NeoForge => "neoforge",
+            ModLoaderType::Fabric => "fabric",
+            ModLoaderType::Quilt => "quilt",
+            ModLoaderType::OptiFine => "optifine",
+            ModLoaderType::LiteLoader => "liteloader",
+        }
+    }
+}
+
+/// The loader of an instance and its version, stored as `<loader>-<version>` (`fabric-0.14.21`)
+/// and as an empty string for vanilla instances.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum InstanceModLoader {
+    #[default]
+    Vanilla,
+    Forge(String),
+    NeoForge(String),
+    Fabric(String),
+    Quilt(String),
+    OptiFine(String),
+    LiteLoader(String),
+}
+
+impl InstanceModLoader {
+    pub fn new(loader_type: ModLoaderType, version: &str) -> Self {
+        let version: String = version.to_string();
+        match loader_type {
+            ModLoaderType::Forge => InstanceModLoader::Forge(version),
+            ModLoaderType::NeoForge => InstanceModLoader::NeoForge(version),
+            ModLoaderType::Fabric => InstanceModLoader::Fabric(version),
+            ModLoaderType::Quilt => InstanceModLoader::Quilt(version),
+            ModLoaderType::OptiFine => InstanceModLoader::OptiFine(version),
+            ModLoaderType::LiteLoader => InstanceModLoader::LiteLoader(version),
+        }
+    }
+
+    pub fn loader_type(&self) -> Option<ModLoaderType> {
+        match self {
+            InstanceModLoader::Vanilla => None,
+            InstanceModLoader::Forge(_) => Some(ModLoaderType::Forge),
+            InstanceModLoader::NeoForge(_) => Some(ModLoaderType::NeoForge),
+            InstanceModLoader::Fabric(_) => Some(ModLoaderType::Fabric),
+            InstanceModLoader::Quilt(_) => Some(ModLoaderType::Quilt),
+            InstanceModLoader::OptiFine(_) => Some(ModLoaderType::OptiFine),
+            InstanceModLoader::LiteLoader(_) => Some(ModLoaderType::LiteLoader),
+        }
+    }
+
+    pub fn version(&self) -> &str {
+        match self {
+            InstanceModLoader::Vanilla => "",
+            InstanceModLoader::Forge(version)
+            | InstanceModLoader::NeoForge(version)
+            | InstanceModLoader::Fabric(version)
+            | InstanceModLoader::Quilt(version)
+            | InstanceModLoader::OptiFine(version)
+            | InstanceModLoader::LiteLoader(version) => version,
+        }
+    }
+
+    /// Loaders that only add a launchwrapper tweaker, so they can be stacked on another loader.
+    pub fn is_addon(&self) -> bool {
+        matches!(
+            self,
+            InstanceModLoader::LiteLoader(_) | InstanceModLoader::OptiFine(_)
+        )
+    }
+}
+
+impl TryFrom<String> for InstanceModLoader {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Ok(InstanceModLoader::Vanilla);
+        }
+        for loader_type in ModLoaderType::ALL {
+            if let Some(version) = value.strip_prefix(&format!("{}-", loader_type.name())) {
+                if !version.is_empty() {
+                    return Ok(InstanceModLoader::new(loader_type, version));
+                }
+            }
+        }
+        Err(format!("Unknown modloader {value}"))
+    }
+}
+
+impl From<InstanceModLoader> for String {
+    fn from(value: InstanceModLoader) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for InstanceModLoader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.loader_type() {
+            Some(loader_type) => write!(f, "{}-{}", loader_type.name(), self.version()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A game version known to the Fabric or Quilt meta.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FabricGameVersion {
+    pub version: String,
+    pub stable: bool,
+    /// Whether the intermediary mappings the loaders run on exist for this version.
+    pub intermediary: bool,
+}
+
+/// A Fabric or Quilt loader build.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FabricLoaderVersion {
+    pub version: String,
+    pub stable: bool,
+    pub maven: String,
+}
+
+/// How a Forge build gets installed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForgeInstallKind {
+    /// The oldest builds, merged into the client jar.
+    Patches,
+    /// Installer builds launched from the universal jar, up to 1.12.2.
+    Universal,
+    /// Installer builds whose processors generate the client jar, 1.13 onwards.
+    Processors,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ForgeBuild {
+    pub version: String,
+    pub game_version: String,
+    pub recommended: bool,
+    pub latest: bool,
+    /// From the version json once installed, until then when Forge published its metadata.
+    /// `None` when neither could be fetched.
+    pub release_time: Option<String>,
+    pub install_kind: ForgeInstallKind,
+}
+
+/// Filters for the version list, an empty field matches every version.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct VersionQuery {
+    /// Versions of any of these types.
+    #[serde(default)]
+    pub types: Vec<VersionType>,
+    /// Versions with builds for any of these loaders.
+    #[serde(default)]
+    pub modloaders: Vec<ModLoaderType>,
+    /// Case insensitive text the version id has to contain.
+    #[serde(default)]
+    pub search: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionSource {
+    #[default]
+    Mojang,
+    BetterJsons,
+    Extra,
+}
+
+/// An entry of the merged version manifest.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VersionManifestEntry {
+    pub id: String,
+    #[serde(default)]
+    pub r#type: String,
+    pub url: String,
+    #[serde(default)]
+    pub sha1: String,
+    #[serde(default, rename = "releaseTime")]
+    pub release_time: String,
+    #[serde(default)]
+    pub source: VersionSource,
+    /// The untouched Mojang json, forge can't launch these versions through the BetterJSONs one.
+    #[serde(default)]
+    pub forge_compatible: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -18,7 +242,118 @@ pub struct InstanceInfo {
     pub height: String,
     pub width: String,
     pub fullscreen: bool,
-    pub modloader: String,
+    pub modloader: InstanceModLoader,
+    /// Launchwrapper loaders stacked on top of `modloader`, like LiteLoader on Forge.
+    #[serde(default)]
+    pub addons: Vec<InstanceModLoader>,
+    /// Passed to java after the launcher defaults, so they take precedence.
+    #[serde(default)]
+    pub jvm_args: Vec<String>,
+    /// A game folder outside the launcher, for instances linked to another launcher's folder.
+    #[serde(default)]
+    pub game_dir: Option<String>,
+}
+
+impl InstanceInfo {
+    /// A fresh instance with the default looks.
+    pub fn new(name: &str, version: &str, modloader: &InstanceModLoader) -> Self {
+        InstanceInfo {
+            name: String::from(name),
+            version: String::from(version),
+            background: String::from("default0"),
+            icon: String::from("default0"),
+            width: String::from("1920"),
+            height: String::from("1080"),
+            modloader: modloader.clone(),
+            fullscreen: false,
+            addons: vec![],
+            jvm_args: vec![],
+            game_dir: None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct GarbageEntry {
+    pub path: String,
+    pub size: u64,
+    pub r#type: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct GarbageReport {
+    pub dry_run: bool,
+    pub reclaimable_bytes: u64,
+    pub entries: Vec<GarbageEntry>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RepairReport {
+    pub checked: u64,
+    pub repaired: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+// Launcher
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LauncherSettings {
+    #[serde(default)]
+    pub offline_mode: bool,
+    #[serde(default = "default_metadata_ttl")]
+    pub metadata_ttl_minutes: u64,
+    /// Base url replacing the default host of each remote source, e.g. a BMCLAPI mirror.
+    #[serde(default)]
+    pub mirrors: HashMap<MirrorSource, String>,
+    /// Sent as `x-api-key` to the CurseForge api, proxies usually don't need one.
+    #[serde(default)]
+    pub curseforge_api_key: String,
+}
+
+impl Default for LauncherSettings {
+    fn default() -> Self {
+        LauncherSettings {
+            offline_mode: false,
+            metadata_ttl_minutes: default_metadata_ttl(),
+            mirrors: HashMap::new(),
+            curseforge_api_key: String::new(),
+        }
+    }
+}
+
+fn default_metadata_ttl() -> u64 {
+    30
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MirrorSource {
+    MojangMeta,
+    MojangData,
+    MojangLauncherMeta,
+    MojangLauncher,
+    MojangResources,
+    MojangLibraries,
+    ForgeMaven,
+    ForgeFiles,
+    NeoForgeMaven,
+    FabricMeta,
+    FabricMaven,
+    QuiltMeta,
+    QuiltMaven,
+    Adoptium,
+    Modrinth,
+    /// The CurseForge api, point it to a proxy or a local stand-in.
+    CurseForge,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MetadataCacheEntry {
+    pub url: String,
+    pub path: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub fetched_at: u64,
 }
 
 // Auth
@@ -95,3 +430,289 @@ pub struct DownloadInstanceEventPayload {
     pub downloaded: u64,
     pub name: String,
 }
+
+// Modrinth
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModrinthProjectType {
+    Mod,
+    Modpack,
+    Resourcepack,
+    Shader,
+    Datapack,
+}
+
+impl ModrinthProjectType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModrinthProjectType::Mod => "mod",
+            ModrinthProjectType::Modpack => "modpack",
+            ModrinthProjectType::Resourcepack => "resourcepack",
+            ModrinthProjectType::Shader => "shader",
+            ModrinthProjectType::Datapack => "datapack",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModrinthSortIndex {
+    #[default]
+    Relevance,
+    Downloads,
+    Follows,
+    Newest,
+    Updated,
+}
+
+impl ModrinthSortIndex {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModrinthSortIndex::Relevance => "relevance",
+            ModrinthSortIndex::Downloads => "downloads",
+            ModrinthSortIndex::Follows => "follows",
+            ModrinthSortIndex::Newest => "newest",
+            ModrinthSortIndex::Updated => "updated",
+        }
+    }
+}
+
+/// A Modrinth search, each non empty filter narrows the results and its values are or-ed.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ModrinthSearchQuery {
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub project_types: Vec<ModrinthProjectType>,
+    #[serde(default)]
+    pub game_versions: Vec<String>,
+    /// Loader names as Modrinth knows them, e.g. `fabric` or `neoforge`.
+    #[serde(default)]
+    pub loaders: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub offset: u32,
+    /// Clamped to the 100 hits Modrinth returns at most.
+    #[serde(default)]
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub index: ModrinthSortIndex,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModrinthSearchHit {
+    pub project_id: String,
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// Kept as text, Modrinth also lists plugins and datapacks it files as mods.
+    pub project_type: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Game versions the project has files for.
+    #[serde(default)]
+    pub versions: Vec<String>,
+    #[serde(default)]
+    pub downloads: u64,
+    #[serde(default)]
+    pub follows: u64,
+    #[serde(default)]
+    pub icon_url: Option<String>,
+    #[serde(default)]
+    pub featured_gallery: Option<String>,
+    #[serde(default)]
+    pub date_modified: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ModrinthSearchResult {
+    pub hits: Vec<ModrinthSearchHit>,
+    pub offset: u32,
+    pub limit: u32,
+    pub total_hits: u32,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ModrinthHashes {
+    #[serde(default)]
+    pub sha1: String,
+    #[serde(default)]
+    pub sha512: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModrinthVersionFile {
+    pub url: String,
+    pub filename: String,
+    #[serde(default)]
+    pub primary: bool,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub hashes: ModrinthHashes,
+}
+
+/// A published version of a Modrinth project.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModrinthVersion {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub version_number: String,
+    /// `release`, `beta` or `alpha`.
+    #[serde(default)]
+    pub version_type: String,
+    #[serde(default)]
+    pub game_versions: Vec<String>,
+    #[serde(default)]
+    pub loaders: Vec<String>,
+    #[serde(default)]
+    pub date_published: String,
+    pub files: Vec<ModrinthVersionFile>,
+}
+
+impl ModrinthVersion {
+    /// The file Modrinth flags as primary, or the first one.
+    pub fn primary_file(&self) -> Option<&ModrinthVersionFile> {
+        self.files
+            .iter()
+            .find(|file| file.primary)
+            .or(self.files.first())
+    }
+}
+
+/// What goes into an exported `.mrpack`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MrpackExportOptions {
+    /// The pack name, the instance name when empty.
+    #[serde(default)]
+    pub name: String,
+    pub version_id: String,
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// Paths relative to the instance folder, every file is exported when empty.
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Folders or files relative to the instance folder that are never exported.
+    #[serde(default = "default_export_exclusions")]
+    pub exclude: Vec<String>,
+}
+
+/// What goes into a portable instance archive.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstanceArchiveOptions {
+    /// Folders or files relative to the instance folder that are left out.
+    #[serde(default = "default_export_exclusions")]
+    pub exclude: Vec<String>,
+}
+
+pub fn default_export_exclusions() -> Vec<String> {
+    ["saves", "logs", "crash-reports", "screenshots"]
+        .iter()
+        .map(|path| path.to_string())
+        .collect()
+}
+
+// CurseForge
+
+/// A modpack file CurseForge doesn't allow third party launchers to download.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CurseForgeMissingFile {
+    pub project_id: u64,
+    pub file_id: u64,
+    pub project_name: String,
+    pub file_name: String,
+    /// Where the user can download the file by hand.
+    pub website_url: String,
+    /// Where the file goes, relative to the instance folder.
+    pub path: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CurseForgeImportReport {
+    pub instance: InstanceInfo,
+    pub missing: Vec<CurseForgeMissingFile>,
+}
+
+// Other launchers
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalLauncher {
+    /// MultiMC and the launchers forked from it, like Prism Launcher.
+    MultiMc,
+    /// The official launcher.
+    Vanilla,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalImportMode {
+    /// Copies the game folder into the new instance.
+    #[default]
+    Copy,
+    /// Keeps using the game folder where it is.
+    Link,
+}
+
+/// An instance or profile of another launcher, mapped to what an instance of ours needs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExternalInstance {
+    /// The instance folder name for MultiMC, the profile key for the official launcher.
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub modloader: InstanceModLoader,
+    pub addons: Vec<InstanceModLoader>,
+    pub game_dir: String,
+    pub jvm_args: Vec<String>,
+}
+
+// Mods
+
+/// Where the metadata of a mod was read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModMetadataFormat {
+    /// `fabric.mod.json`
+    Fabric,
+    /// `quilt.mod.json`
+    Quilt,
+    /// `META-INF/mods.toml`
+    Forge,
+    /// `META-INF/neoforge.mods.toml`
+    NeoForge,
+    /// The `mcmod.info` of Forge before 1.13.
+    LegacyForge,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModDependency {
+    pub id: String,
+    /// The version range as declared by the mod, empty when any version does.
+    pub version: String,
+    pub required: bool,
+}
+
+/// A jar in the mods folder of an instance. Jars without metadata are listed with the file
+/// name only.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstalledMod {
+    /// The file name without `.disabled`.
+    pub file_name: String,
+    pub enabled: bool,
+    pub format: Option<ModMetadataFormat>,
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub authors: Vec<String>,
+    pub dependencies: Vec<ModDependency>,
+    /// The bundled icon as a `data:` url.
+    pub icon: Option<String>,
+}
@@ -14,8 +14,7 @@ use common::{
         self,
         versions::{get_fabric_loader_versions, get_fabric_mc_versions},
     },
-    modpacks::modrinth::fetch_modpacks,
-    utils,
+    modloader, modpacks, utils,
 };
 use data::models::{self, InstanceInfo};
 
@@ -27,6 +26,16 @@ async fn get_minecraft_versions() -> Result<Vec<models::MinecraftVersionData>, (
     }
 }
 
+#[tauri::command]
+async fn query_minecraft_versions(
+    query: models::VersionQuery,
+) -> Result<Vec<models::MinecraftVersionData>, ()> {
+    match minecraft::versions::query_versions(&query).await {
+        Ok(version_list) => Ok(version_list),
+        Err(_) => Ok([].to_vec()),
+    }
+}
+
 #[tauri::command]
 async fn get_forge_versions() -> Result<Value, ()> {
     match minecraft::versions::get_forge_versions().await {
@@ -35,6 +44,17 @@ async fn get_forge_versions() -> Result<Value, ()> {
     }
 }
 
+#[tauri::command]
+async fn get_forge_builds(game_version: &str) -> Result<Vec<models::ForgeBuild>, ()> {
+    match modloader::forge::get_builds(game_version).await {
+        Ok(builds) => Ok(builds),
+        Err(err) => {
+            write_line(&format!("Failed to list the Forge builds for {game_version}: {err}"));
+            Ok(vec![])
+        }
+    }
+}
+
 #[tauri::command]
 async fn start_oauth(handle: tauri::AppHandle) {
     auth::login::create_login_window(handle);
@@ -46,8 +66,14 @@ fn get_accounts() -> Vec<models::MinecraftAccount> {
 }
 
 #[tauri::command]
-fn set_active_account(uuid: &str) {
-    auth::login::set_active_account(uuid);
+fn set_active_account(uuid: &str) -> Result<(), ()> {
+    match auth::login::set_active_account(uuid) {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            write_line(&format!("Error switching to account {uuid}: {err}"));
+            Err(())
+        }
+    }
 }
 
 #[tauri::command]
@@ -67,7 +93,15 @@ async fn create_instance(
     modloader: &str,
     handle: tauri::AppHandle,
 ) -> Result<(), ()> {
-    minecraft::instance::create_instance(id, name, modloader, &handle).await;
+    let modloader: models::InstanceModLoader =
+        match models::InstanceModLoader::try_from(modloader.to_string()) {
+            Ok(modloader) => modloader,
+            Err(err) => {
+                write_line(&format!("Error creating {name}: {err}"));
+                return Err(());
+            }
+        };
+    minecraft::instance::create_instance(id, name, &modloader, &handle).await;
     Ok(())
 }
 
@@ -87,6 +121,54 @@ fn open_instance_folder(name: &str) {
     minecraft::instance::open_folder(name);
 }
 
+#[tauri::command]
+async fn clone_instance(
+    name: &str,
+    new_name: &str,
+    exclude: Vec<String>,
+) -> Result<InstanceInfo, ()> {
+    match minecraft::instance::clone_instance(name, new_name, &exclude).await {
+        Ok(instance) => Ok(instance),
+        Err(err) => {
+            write_line(&format!("Error cloning {name}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn list_mods(name: &str) -> Result<Vec<models::InstalledMod>, ()> {
+    match minecraft::mods::list_mods(name).await {
+        Ok(mods) => Ok(mods),
+        Err(err) => {
+            write_line(&format!("Error listing the mods of {name}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn inspect_mod(name: &str, file_name: &str) -> Result<models::InstalledMod, ()> {
+    match minecraft::mods::inspect_mod(name, file_name).await {
+        Ok(installed) => Ok(installed),
+        Err(err) => {
+            write_line(&format!("Error reading {file_name} of {name}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn set_mod_enabled(name: &str, file_name: &str, enabled: bool) -> Result<(), ()> {
+    match minecraft::mods::set_mod_enabled(name, file_name, enabled).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            write_line(&format!("Error toggling {file_name} of {name}: {err}"));
+            Err(())
+        }
+    }
+}
+
 #[tauri::command]
 async fn write_instance_data(
     name: &str,
@@ -97,9 +179,115 @@ async fn write_instance_data(
     Ok(())
 }
 
+#[tauri::command]
+async fn change_instance_version(
+    name: &str,
+    version: &str,
+    modloader: &str,
+    handle: tauri::AppHandle,
+) -> Result<InstanceInfo, ()> {
+    let modloader: models::InstanceModLoader =
+        match models::InstanceModLoader::try_from(modloader.to_string()) {
+            Ok(modloader) => modloader,
+            Err(err) => {
+                write_line(&format!("Error changing the version of {name}: {err}"));
+                return Err(());
+            }
+        };
+    match minecraft::instance::change_version(name, version, &modloader, &handle).await {
+        Ok(instance) => Ok(instance),
+        Err(_) => Err(()),
+    }
+}
+
+#[tauri::command]
+async fn set_instance_addons(
+    name: &str,
+    addons: Vec<models::InstanceModLoader>,
+    handle: tauri::AppHandle,
+) -> Result<(), ()> {
+    match minecraft::instance::set_addons(name, addons, &handle).await {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            write_line(&format!("Error setting the addons of {name}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn collect_garbage(dry_run: bool) -> Result<models::GarbageReport, ()> {
+    match minecraft::storage::collect_garbage(dry_run).await {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            write_line(&format!("Error collecting garbage: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn repair_instance(
+    name: &str,
+    handle: tauri::AppHandle,
+) -> Result<models::RepairReport, ()> {
+    match minecraft::repair::repair_instance(name, &handle).await {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            write_line(&format!("Error repairing {name}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn get_modloader_versions(
+    loader: models::ModLoaderType,
+    game_version: &str,
+) -> Result<Vec<String>, ()> {
+    match modloader::loader::list_versions(loader, game_version).await {
+        Ok(versions) => Ok(versions),
+        Err(_) => Ok(vec![]),
+    }
+}
+
+#[tauri::command]
+async fn get_latest_modloader_version(
+    loader: models::ModLoaderType,
+    game_version: &str,
+) -> Result<Option<String>, ()> {
+    match modloader::loader::latest_stable(loader, game_version).await {
+        Ok(version) => Ok(version),
+        Err(_) => Ok(None),
+    }
+}
+
+// optifine
+#[tauri::command]
+async fn import_optifine_installer(path: &str) -> Result<String, ()> {
+    match modloader::optifine::import_installer(path).await {
+        Ok(version) => Ok(version),
+        Err(err) => {
+            write_line(&format!("Error importing {path}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn add_optifine_to_instance(name: &str, version: &str) -> Result<(), ()> {
+    match modloader::optifine::install_as_mod(name, version).await {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            write_line(&format!("Error adding OptiFine to {name}: {err}"));
+            Err(())
+        }
+    }
+}
+
 // fabric and quilt
 #[tauri::command]
-async fn get_fabric_minecraft_versions(is_quilt: bool) -> Vec<Value> {
+async fn get_fabric_minecraft_versions(is_quilt: bool) -> Vec<models::FabricGameVersion> {
     match get_fabric_mc_versions(is_quilt).await {
         Ok(versions) => versions,
         Err(_) => [].to_vec(),
@@ -107,7 +295,7 @@ async fn get_fabric_minecraft_versions(is_quilt: bool) -> Vec<Value> {
 }
 
 #[tauri::command]
-async fn get_fabric_versions(is_quilt: bool) -> Vec<Value> {
+async fn get_fabric_versions(is_quilt: bool) -> Vec<models::FabricLoaderVersion> {
     match get_fabric_loader_versions(is_quilt).await {
         Ok(versions) => versions,
         Err(_) => [].to_vec(),
@@ -115,10 +303,186 @@ async fn get_fabric_versions(is_quilt: bool) -> Vec<Value> {
 }
 
 #[tauri::command]
-async fn get_modrinth_modpacks() -> Value {
-    match fetch_modpacks().await {
-        Ok(value) => value,
-        Err(_) => serde_json::from_str("[\"hits\": []]").unwrap(),
+async fn search_modrinth(
+    query: models::ModrinthSearchQuery,
+) -> Result<models::ModrinthSearchResult, ()> {
+    match modpacks::modrinth::search(&query).await {
+        Ok(result) => Ok(result),
+        Err(err) => {
+            write_line(&format!("Error searching Modrinth: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn install_mrpack(
+    path: &str,
+    name: &str,
+    handle: tauri::AppHandle,
+) -> Result<InstanceInfo, ()> {
+    match modpacks::mrpack::install_from_file(path, name, &handle).await {
+        Ok(instance) => Ok(instance),
+        Err(err) => {
+            write_line(&format!("Error installing {path}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn install_modrinth_modpack(
+    project_id: &str,
+    version_id: Option<&str>,
+    name: &str,
+    handle: tauri::AppHandle,
+) -> Result<InstanceInfo, ()> {
+    match modpacks::mrpack::install_from_modrinth(project_id, version_id, name, &handle).await {
+        Ok(instance) => Ok(instance),
+        Err(err) => {
+            write_line(&format!("Error installing the modpack {project_id}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn list_instance_files(name: &str) -> Result<Vec<String>, ()> {
+    match modpacks::mrpack::list_instance_files(name).await {
+        Ok(files) => Ok(files),
+        Err(err) => {
+            write_line(&format!("Error listing the files of {name}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn export_mrpack(
+    name: &str,
+    options: models::MrpackExportOptions,
+    output: &str,
+) -> Result<(), ()> {
+    match modpacks::mrpack::export(name, &options, output).await {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            write_line(&format!("Error exporting {name}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn import_curseforge_modpack(
+    path: &str,
+    name: &str,
+    handle: tauri::AppHandle,
+) -> Result<models::CurseForgeImportReport, ()> {
+    match modpacks::curseforge::import(path, name, &handle).await {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            write_line(&format!("Error importing {path}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn export_instance_archive(
+    name: &str,
+    options: models::InstanceArchiveOptions,
+    output: &str,
+) -> Result<(), ()> {
+    match modpacks::archive::export(name, &options, output).await {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            write_line(&format!("Error exporting {name}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn import_instance_archive(
+    path: &str,
+    name: Option<&str>,
+    handle: tauri::AppHandle,
+) -> Result<InstanceInfo, ()> {
+    match modpacks::archive::import(path, name, &handle).await {
+        Ok(instance) => Ok(instance),
+        Err(err) => {
+            write_line(&format!("Error importing {path}: {err}"));
+            Err(())
+        }
+    }
+}
+
+// other launchers
+#[tauri::command]
+async fn list_external_instances(
+    launcher: models::ExternalLauncher,
+    path: &str,
+) -> Result<Vec<models::ExternalInstance>, ()> {
+    match modpacks::import::list_external_instances(launcher, path).await {
+        Ok(instances) => Ok(instances),
+        Err(err) => {
+            write_line(&format!("Error reading the instances in {path}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+async fn import_external_instance(
+    launcher: models::ExternalLauncher,
+    path: &str,
+    id: &str,
+    name: &str,
+    mode: models::ExternalImportMode,
+    handle: tauri::AppHandle,
+) -> Result<InstanceInfo, ()> {
+    match modpacks::import::import_external(launcher, path, id, name, mode, &handle).await {
+        Ok(instance) => Ok(instance),
+        Err(err) => {
+            write_line(&format!("Error importing {id} from {path}: {err}"));
+            Err(())
+        }
+    }
+}
+
+#[tauri::command]
+fn get_offline_mode() -> bool {
+    utils::network::is_offline()
+}
+
+#[tauri::command]
+async fn set_offline_mode(offline: bool) -> bool {
+    let mut settings: models::LauncherSettings = utils::settings::get_settings().await;
+    settings.offline_mode = offline;
+    if let Err(err) = utils::settings::write_settings(&settings) {
+        write_line(&format!("Error saving settings: {err}"));
+    }
+    utils::network::detect_offline_mode().await
+}
+
+#[tauri::command]
+fn get_metadata_status() -> Vec<models::MetadataCacheEntry> {
+    utils::cache::get_cache_entries()
+}
+
+#[tauri::command]
+async fn get_launcher_settings() -> models::LauncherSettings {
+    utils::settings::get_settings().await
+}
+
+#[tauri::command]
+fn set_launcher_settings(settings: models::LauncherSettings) -> Result<(), ()> {
+    match utils::settings::write_settings(&settings) {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            write_line(&format!("Error saving settings: {err}"));
+            Err(())
+        }
     }
 }
 
@@ -131,6 +495,7 @@ async fn main() {
     tauri::Builder::default()
         .invoke_handler(tauri::generate_handler![
             get_minecraft_versions,
+            query_minecraft_versions,
             start_oauth,
             get_accounts,
             set_active_account,
@@ -140,21 +505,53 @@ async fn main() {
             launch_instance,
             remove_instance,
             open_instance_folder,
+            clone_instance,
+            list_mods,
+            inspect_mod,
+            set_mod_enabled,
             write_instance_data,
+            change_instance_version,
+            set_instance_addons,
+            collect_garbage,
+            repair_instance,
             get_forge_versions,
+            get_forge_builds,
             get_fabric_minecraft_versions,
             get_fabric_versions,
-            get_modrinth_modpacks,
+            get_modloader_versions,
+            get_latest_modloader_version,
+            import_optifine_installer,
+            add_optifine_to_instance,
+            search_modrinth,
+            install_mrpack,
+            install_modrinth_modpack,
+            list_instance_files,
+            export_mrpack,
+            import_curseforge_modpack,
+            export_instance_archive,
+            import_instance_archive,
+            list_external_instances,
+            import_external_instance,
+            get_offline_mode,
+            set_offline_mode,
+            get_metadata_status,
+            get_launcher_settings,
+            set_launcher_settings,
         ])
         .setup(|app| {
             let handle: AppHandle = app.handle();
-            // refresh ms tokens
             tauri::async_runtime::spawn(async move {
-                auth::bearer_token::refresh_bearer_tokens(&handle).await;
-            });
+                if utils::network::detect_offline_mode().await {
+                    write_line("Starting in offline mode, using the cached manifests");
+                    return;
+                }
 
-            // update the version manifest
-            tauri::async_runtime::spawn(async move {
+                // refresh ms tokens
+                tauri::async_runtime::spawn(async move {
+                    auth::bearer_token::refresh_bearer_tokens(&handle).await;
+                });
+
+                // update the version manifest
                 match minecraft::versions::download_version_manifests().await {
                     Ok(_) => println!("Version manifest successfully updated"),
                     Err(err) => {
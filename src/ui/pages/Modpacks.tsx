@@ -24,7 +24,7 @@ function Modpacks(): JSX.Element {
     const [modpacks, setModpacks] = useState<Modpack[]>([]);
 
     function getModpacks(): void {
-        invoke('get_modrinth_modpacks')
+        invoke('search_modrinth', { query: { project_types: ['modpack'], limit: 50 } })
             .then((mp) => {
                 const newModpacks = (mp as ModrinthResponse).hits;
                 setModpacks(newModpacks);